@@ -1,5 +1,7 @@
+use crate::errors::BlJaError;
 use std::fmt::{Display, Formatter};
 use std::ops::{Deref, DerefMut};
+use std::str::FromStr;
 
 /// The most basic representation of a playing card.    
 /// The `denomination` is the number of letter
@@ -8,6 +10,7 @@ use std::ops::{Deref, DerefMut};
 /// depends on the specific game, so implementations of those attributes
 /// will be handled in respective modules by use of traits.
 #[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Card {
     denomination: Denomination,
     suit: Suit,
@@ -18,6 +21,7 @@ pub trait BlackJackScore {
 }
 
 #[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum Denomination {
     Ace,
     King,
@@ -27,7 +31,45 @@ pub enum Denomination {
     Extra(&'static str),
 }
 
+/// A `Denomination` as it is loaded.  Extra cards, such as the cut card, are only ever made from string
+/// literals, so they are matched back to their literal.  Any other extra card is rejected.
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+#[serde(rename = "Denomination")]
+enum SavedDenomination {
+    Ace,
+    King,
+    Queen,
+    Jack,
+    Numerical(usize),
+    Extra(String),
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Denomination {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let saved = <SavedDenomination as serde::Deserialize>::deserialize(deserializer)?;
+        Ok(match saved {
+            SavedDenomination::Ace => Denomination::Ace,
+            SavedDenomination::King => Denomination::King,
+            SavedDenomination::Queen => Denomination::Queen,
+            SavedDenomination::Jack => Denomination::Jack,
+            SavedDenomination::Numerical(v) => Denomination::Numerical(v),
+            SavedDenomination::Extra(extra) => match extra.as_str() {
+                "shuffle" => Denomination::Extra("shuffle"),
+                _ => {
+                    return Err(serde::de::Error::custom(format!(
+                        "unknown extra card: {}",
+                        extra
+                    )))
+                }
+            },
+        })
+    }
+}
+
 #[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Suit {
     Clubs,
     Spades,
@@ -37,6 +79,7 @@ pub enum Suit {
 
 // Similar to `Option`, but the `None` equivalent value carrier a card.  Must be flipped to be unwraped.
 #[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Visible<T> {
     FacedUp(T),
     FacedDown(T),
@@ -110,6 +153,64 @@ impl Display for Card {
     }
 }
 
+/// Parses a denomination as shown by `Display`, ignoring case.  Tens can also be written as "T".
+impl FromStr for Denomination {
+    type Err = BlJaError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        use Denomination::*;
+        Ok(match s.to_ascii_uppercase().as_str() {
+            "A" => Ace,
+            "K" => King,
+            "Q" => Queen,
+            "J" => Jack,
+            "T" | "10" => Numerical(10),
+            d => match d.parse() {
+                Ok(v) if (2..=9).contains(&v) => Numerical(v),
+                _ => return Err(BlJaError::InvalidCard(s.to_string())),
+            },
+        })
+    }
+}
+
+/// Parses a suit from its letter, ignoring case, or from its symbol either filled or as shown by `Display`.
+impl FromStr for Suit {
+    type Err = BlJaError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        use Suit::*;
+        let mut chars = s.chars();
+        let suit = match (chars.next(), chars.next()) {
+            (Some(c), None) => match c {
+                'C' | 'c' | '\u{2663}' | '\u{2667}' => Clubs,
+                'S' | 's' | '\u{2660}' | '\u{2664}' => Spades,
+                'H' | 'h' | '\u{2661}' | '\u{2665}' => Hearts,
+                'D' | 'd' | '\u{2662}' | '\u{2666}' => Diamonds,
+                _ => return Err(BlJaError::InvalidCard(s.to_string())),
+            },
+            _ => return Err(BlJaError::InvalidCard(s.to_string())),
+        };
+        Ok(suit)
+    }
+}
+
+/// Parses a card written as its denomination followed by its suit, such as "10H", "As", "Q\u{2660}", or the
+/// "A \u{2660}" shown by `Display`.
+impl FromStr for Card {
+    type Err = BlJaError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || BlJaError::InvalidCard(s.to_string());
+        let s = s.trim();
+        let (split, _) = s.char_indices().last().ok_or_else(invalid)?;
+        let (denomination, suit) = s.split_at(split);
+        Ok(Card::new(
+            denomination.trim_end().parse().map_err(|_| invalid())?,
+            suit.parse().map_err(|_| invalid())?,
+        ))
+    }
+}
+
 impl BlackJackScore for Card {
     fn score(&self) -> usize {
         use Denomination::*;
@@ -213,4 +314,68 @@ mod cardtests {
         assert_eq!(eight.denom(), Denomination::Numerical(8));
         assert_eq!(eight.suit, Suit::Hearts);
     }
+
+    #[test]
+    fn parse() -> Result<(), Box<dyn Error>> {
+        assert_eq!(
+            "AS".parse::<Card>()?,
+            Card::new(Denomination::Ace, Suit::Spades)
+        );
+        assert_eq!(
+            "KD".parse::<Card>()?,
+            Card::new(Denomination::King, Suit::Diamonds)
+        );
+        assert_eq!(
+            "5H".parse::<Card>()?,
+            Card::new(Denomination::Numerical(5), Suit::Hearts)
+        );
+        let ten = Card::new(Denomination::Numerical(10), Suit::Clubs);
+        assert_eq!("10C".parse::<Card>()?, ten);
+        assert_eq!("TC".parse::<Card>()?, ten);
+
+        // Lowercase, symbols, and the `Display` format
+        let queen = Card::new(Denomination::Queen, Suit::Spades);
+        assert_eq!("qs".parse::<Card>()?, queen);
+        assert_eq!("Q\u{2660}".parse::<Card>()?, queen);
+        assert_eq!("Q \u{2664}".parse::<Card>()?, queen);
+        assert_eq!("As".parse::<Card>()?.denom(), Denomination::Ace);
+        assert_eq!("10\u{2665}".parse::<Card>()?.suit(), Suit::Hearts);
+        assert_eq!("2\u{2666}".parse::<Card>()?.suit(), Suit::Diamonds);
+
+        let suits = [Suit::Clubs, Suit::Spades, Suit::Hearts, Suit::Diamonds];
+        let denoms = (2..=10).map(Denomination::Numerical).chain(vec![
+            Denomination::Ace,
+            Denomination::King,
+            Denomination::Queen,
+            Denomination::Jack,
+        ]);
+        for denom in denoms {
+            assert_eq!(denom.to_string().parse::<Denomination>()?, denom);
+            for suit in suits.iter() {
+                let card = Card::new(denom, *suit);
+                assert_eq!(card.to_string().parse::<Card>()?, card);
+                assert_eq!(suit.to_string().parse::<Suit>()?, *suit);
+            }
+        }
+
+        for bad in [
+            "",
+            "A",
+            "S",
+            "1S",
+            "11S",
+            "AX",
+            "ASS",
+            "A\u{2660}\u{2660}",
+            "10 H S",
+        ]
+        .iter()
+        {
+            assert_eq!(
+                bad.parse::<Card>(),
+                Err(BlJaError::InvalidCard(bad.to_string()))
+            );
+        }
+        Ok(())
+    }
 }
@@ -0,0 +1,522 @@
+//! Table settings read from a file, such as a `blackjack.toml` kept for each table variant.  Command line
+//! options override the settings in the file.  Reading a file needs the `serde` feature.
+//!
+//! ```toml
+//! decks = 6
+//! bankroll = 250.50
+//! count = "Zen"
+//! trainer = true
+//! names = ["Ann", "Bob"]
+//!
+//! [rules]
+//! dealer_hits_soft_17 = true
+//! double_after_split = false
+//! surrender = "late"
+//! blackjack_payout = "6:5"
+//! min_bet = 10
+//! max_bet = 500
+//! penetration = "75%"
+//! jitter = 10
+//! burn = 1
+//! ```
+
+use crate::counting::CountingSystem;
+use crate::rules::TableRules;
+use crate::Res;
+use num::Rational64;
+#[cfg(feature = "serde")]
+use std::fs;
+use std::path::Path;
+
+/// Read when no config file is given, if it is in the working directory.
+pub const DEFAULT_CONFIG: &str = "blackjack.toml";
+
+/// Settings from a config file.  Settings the file leaves out are `None`, or the default rules.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Config {
+    pub players: Option<usize>,
+    pub names: Option<Vec<String>>,
+    pub bankroll: Option<Rational64>,
+    pub decks: Option<usize>,
+    pub count: Option<CountingSystem>,
+    pub trainer: Option<bool>,
+    pub rules: TableRules,
+}
+
+impl Config {
+    #[cfg(feature = "serde")]
+    pub fn read(path: &Path) -> Res<Config> {
+        Ok(fs::read_to_string(path)?.parse()?)
+    }
+
+    #[cfg(not(feature = "serde"))]
+    pub fn read(_path: &Path) -> Res<Config> {
+        Err(Box::new(crate::errors::BlJaError::ImproperAction(
+            "Reading config files needs the serde feature",
+        )))
+    }
+}
+
+// The file is read into `Settings` by the toml crate, then each setting is checked
+#[cfg(feature = "serde")]
+mod settings {
+    use super::Config;
+    use crate::errors::BlJaError;
+    use crate::player::parse_money;
+    use crate::rules::{CutCard, DealingMode, DoubleRule, Penetration, SurrenderRule, TableRules};
+    use num::{Rational64, Zero};
+    use serde::Deserialize;
+    use std::convert::TryFrom;
+    use std::str::FromStr;
+
+    /// The settings as written in the file, before they are checked.
+    #[derive(Debug, Default, Deserialize)]
+    #[serde(deny_unknown_fields)]
+    struct Settings {
+        players: Option<usize>,
+        names: Option<Vec<String>>,
+        bankroll: Option<toml::Value>,
+        decks: Option<usize>,
+        count: Option<String>,
+        trainer: Option<bool>,
+        #[serde(default)]
+        rules: RuleSettings,
+    }
+
+    #[derive(Debug, Default, Deserialize)]
+    #[serde(deny_unknown_fields)]
+    struct RuleSettings {
+        min_bet: Option<toml::Value>,
+        max_bet: Option<toml::Value>,
+        dealer_hits_soft_17: Option<bool>,
+        blackjack_payout: Option<String>,
+        insurance_payout: Option<String>,
+        double: Option<String>,
+        double_after_split: Option<bool>,
+        max_hands: Option<usize>,
+        resplit_aces: Option<bool>,
+        hit_split_aces: Option<bool>,
+        surrender: Option<String>,
+        dealing: Option<String>,
+        penetration: Option<toml::Value>,
+        jitter: Option<usize>,
+        burn: Option<usize>,
+        burn_face_up: Option<bool>,
+    }
+
+    impl FromStr for Config {
+        type Err = BlJaError;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            let settings: Settings = toml::from_str(s).map_err(|e| BlJaError::ConfigSyntax {
+                // Spans are byte offsets into the file
+                line: e
+                    .span()
+                    .map_or(1, |span| s[..span.start].matches('\n').count() + 1),
+                reason: e.message().to_string(),
+            })?;
+            let config = Config::try_from(settings)?;
+            config.validate()?;
+            Ok(config)
+        }
+    }
+
+    impl TryFrom<Settings> for Config {
+        type Error = BlJaError;
+
+        fn try_from(settings: Settings) -> Result<Self, Self::Error> {
+            let mut config = Config {
+                players: settings
+                    .players
+                    .map(|players| within("players", players, 1, 5))
+                    .transpose()?,
+                decks: settings
+                    .decks
+                    .map(|decks| within("decks", decks, 1, 8))
+                    .transpose()?,
+                trainer: settings.trainer,
+                ..Config::default()
+            };
+            if let Some(names) = settings.names {
+                config.names = Some(check_names(names)?);
+            }
+            if let Some(bankroll) = settings.bankroll {
+                let bankroll = money("bankroll", &bankroll)?;
+                if bankroll.is_zero() {
+                    return Err(invalid("bankroll", "players need money to play"));
+                }
+                config.bankroll = Some(bankroll);
+            }
+            if let Some(count) = settings.count {
+                config.count = Some(count.parse().map_err(|_| {
+                    invalid(
+                        "count",
+                        "expected one of Hi-Lo, KO, Omega II, Zen, Wong Halves",
+                    )
+                })?);
+            }
+            config.rules = table_rules(settings.rules)?;
+            Ok(config)
+        }
+    }
+
+    fn table_rules(settings: RuleSettings) -> Result<TableRules, BlJaError> {
+        let mut rules = TableRules::default();
+        if let Some(min_bet) = settings.min_bet {
+            rules.min_bet = bet("rules.min_bet", &min_bet)?;
+        }
+        if let Some(max_bet) = settings.max_bet {
+            rules.max_bet = Some(bet("rules.max_bet", &max_bet)?);
+        }
+        if let Some(h17) = settings.dealer_hits_soft_17 {
+            rules.dealer_hits_soft_17 = h17;
+        }
+        if let Some(payout) = settings.blackjack_payout {
+            rules.blackjack_payout = ratio("rules.blackjack_payout", &payout)?;
+        }
+        if let Some(payout) = settings.insurance_payout {
+            rules.insurance_payout = ratio("rules.insurance_payout", &payout)?;
+        }
+        if let Some(double) = settings.double {
+            rules.double = match choice("rules.double", &double, &["any", "9-11", "10-11"])? {
+                "9-11" => DoubleRule::NineToEleven,
+                "10-11" => DoubleRule::TenToEleven,
+                _ => DoubleRule::AnyTwo,
+            };
+        }
+        if let Some(das) = settings.double_after_split {
+            rules.double_after_split = das;
+        }
+        if let Some(max_hands) = settings.max_hands {
+            rules.max_hands = within("rules.max_hands", max_hands, 1, 8)?;
+        }
+        if let Some(resplit_aces) = settings.resplit_aces {
+            rules.resplit_aces = resplit_aces;
+        }
+        if let Some(hit_split_aces) = settings.hit_split_aces {
+            rules.hit_split_aces = hit_split_aces;
+        }
+        if let Some(surrender) = settings.surrender {
+            rules.surrender =
+                match choice("rules.surrender", &surrender, &["none", "late", "early"])? {
+                    "late" => SurrenderRule::Late,
+                    "early" => SurrenderRule::Early,
+                    _ => SurrenderRule::NotAllowed,
+                };
+        }
+        if let Some(dealing) = settings.dealing {
+            rules.dealing = match choice(
+                "rules.dealing",
+                &dealing,
+                &["hole-card", "enhc", "enhc-obo"],
+            )? {
+                "enhc" => DealingMode::NoHoleCard {
+                    original_bets_only: false,
+                },
+                "enhc-obo" => DealingMode::NoHoleCard {
+                    original_bets_only: true,
+                },
+                _ => DealingMode::HoleCard,
+            };
+        }
+        // The jitter moves the cut card from the penetration
+        match (settings.penetration, settings.jitter) {
+            (Some(penetration), jitter) => {
+                rules.cut_card = Some(CutCard {
+                    penetration: cut_at("rules.penetration", &penetration)?,
+                    jitter: within("rules.jitter", jitter.unwrap_or(0), 0, 100)?,
+                })
+            }
+            (None, Some(_)) => {
+                return Err(invalid(
+                    "rules.jitter",
+                    "needs a penetration to move the cut card from",
+                ))
+            }
+            (None, None) => (),
+        }
+        if let Some(burn) = settings.burn {
+            rules.burn = within("rules.burn", burn, 0, 10)?;
+        }
+        if let Some(burn_face_up) = settings.burn_face_up {
+            rules.burn_face_up = burn_face_up;
+        }
+        Ok(rules)
+    }
+
+    impl Config {
+        /// Checks the settings that depend on each other.
+        fn validate(&self) -> Result<(), BlJaError> {
+            if matches!(self.rules.max_bet, Some(max) if max < self.rules.min_bet) {
+                return Err(invalid(
+                    "rules.max_bet",
+                    "must not be below the minimum bet",
+                ));
+            }
+            if matches!(self.bankroll, Some(bankroll) if bankroll < self.rules.min_bet) {
+                return Err(invalid("bankroll", "must cover the minimum bet"));
+            }
+            if let (Some(players), Some(names)) = (self.players, &self.names) {
+                if names.len() > players {
+                    return Err(invalid("names", "there are more names than players"));
+                }
+            }
+            Ok(())
+        }
+    }
+
+    fn invalid(key: &str, reason: &str) -> BlJaError {
+        BlJaError::ConfigValue {
+            key: key.to_string(),
+            reason: reason.to_string(),
+        }
+    }
+
+    fn within(key: &str, value: usize, min: usize, max: usize) -> Result<usize, BlJaError> {
+        if (min..=max).contains(&value) {
+            Ok(value)
+        } else {
+            Err(invalid(
+                key,
+                &format!("expected a whole number from {} to {}", min, max),
+            ))
+        }
+    }
+
+    fn check_names(names: Vec<String>) -> Result<Vec<String>, BlJaError> {
+        if names.len() > 5 {
+            return Err(invalid("names", "at most 5 players can be named"));
+        }
+        names
+            .into_iter()
+            .map(|name| match name.trim() {
+                "" => Err(invalid("names", "names cannot be blank")),
+                name => Ok(name.to_string()),
+            })
+            .collect()
+    }
+
+    /// An amount written as a number or a string.  TOML reads decimals as floats, so they are read back
+    /// from their shortest form to keep the amount exact.
+    fn money(key: &str, value: &toml::Value) -> Result<Rational64, BlJaError> {
+        let text = match value {
+            toml::Value::Integer(num) => num.to_string(),
+            toml::Value::Float(num) => num.to_string(),
+            toml::Value::String(text) => text.clone(),
+            _ => String::new(),
+        };
+        parse_money(&text).map_err(|_| invalid(key, "expected an amount such as 500 or 250.50"))
+    }
+
+    /// Bet limits are amounts of money like the bankroll, of at least 1.
+    fn bet(key: &str, value: &toml::Value) -> Result<Rational64, BlJaError> {
+        let bet = money(key, value)?;
+        if bet >= Rational64::from_integer(1) {
+            Ok(bet)
+        } else {
+            Err(invalid(key, "expected an amount of at least 1"))
+        }
+    }
+
+    /// A payout written like "3:2".
+    fn ratio(key: &str, text: &str) -> Result<Rational64, BlJaError> {
+        let mut parts = text.splitn(2, ':').map(|part| part.trim().parse::<i64>());
+        match (parts.next(), parts.next()) {
+            (Some(Ok(win)), Some(Ok(bet))) if win > 0 && bet > 0 => Ok(Rational64::new(win, bet)),
+            _ => Err(invalid(key, "expected a payout such as \"3:2\"")),
+        }
+    }
+
+    fn choice<'a>(key: &str, text: &str, choices: &[&'a str]) -> Result<&'a str, BlJaError> {
+        choices
+            .iter()
+            .find(|choice| choice.eq_ignore_ascii_case(text))
+            .copied()
+            .ok_or_else(|| invalid(key, &format!("expected one of {}", choices.join(", "))))
+    }
+
+    /// A percentage of the shoe such as "75%", or a number of cards.
+    fn cut_at(key: &str, value: &toml::Value) -> Result<Penetration, BlJaError> {
+        let text = match value {
+            toml::Value::Integer(cards) => cards.to_string(),
+            toml::Value::String(text) => text.clone(),
+            _ => String::new(),
+        };
+        text.parse().map_err(|_| {
+            invalid(
+                key,
+                "expected a percentage such as \"75%\" or a number of cards",
+            )
+        })
+    }
+}
+
+#[cfg(all(test, feature = "serde"))]
+mod configtests {
+    use super::*;
+    use crate::errors::BlJaError;
+    use crate::rules::{CutCard, DealingMode, DoubleRule, Penetration, SurrenderRule};
+
+    #[test]
+    fn read_settings() -> Res<()> {
+        let config: Config = r#"
+            # A six deck shoe dealt by the hotel's rules
+            decks = 6
+            bankroll = 250.50
+            count = "wong halves"
+            trainer = true
+            names = [
+                "Ann",    # first base
+                "Bob # 2",
+            ]
+
+            [rules]
+            dealer_hits_soft_17 = true
+            double_after_split = false
+            double = "10-11"
+            surrender = 'late'
+            blackjack_payout = "6:5"
+            min_bet = 10
+            max_bet = 1_000
+            dealing = "enhc-obo"
+            jitter = 8
+            penetration = "80%"
+            burn = 2
+            burn_face_up = true
+        "#
+        .parse()?;
+
+        assert_eq!(config.decks, Some(6));
+        assert_eq!(config.count, Some(crate::counting::WONG_HALVES));
+        assert_eq!(config.trainer, Some(true));
+        assert_eq!(config.players, None);
+        assert_eq!(config.bankroll, Some(Rational64::new(501, 2)));
+        assert_eq!(
+            config.names,
+            Some(vec!["Ann".to_string(), "Bob # 2".to_string()])
+        );
+        assert_eq!(
+            config.rules,
+            TableRules {
+                min_bet: 10.into(),
+                max_bet: Some(1000.into()),
+                dealer_hits_soft_17: true,
+                blackjack_payout: Rational64::new(6, 5),
+                double: DoubleRule::TenToEleven,
+                double_after_split: false,
+                surrender: SurrenderRule::Late,
+                dealing: DealingMode::NoHoleCard {
+                    original_bets_only: true
+                },
+                cut_card: Some(CutCard {
+                    penetration: Penetration::Percent(80),
+                    jitter: 8,
+                }),
+                burn: 2,
+                burn_face_up: true,
+                ..TableRules::default()
+            }
+        );
+
+        // Bet limits are exact amounts, like the bankroll
+        let config: Config = "[rules]\nmin_bet = 2.50\nmax_bet = \"250.50\"".parse()?;
+        assert_eq!(config.rules.min_bet, Rational64::new(5, 2));
+        assert_eq!(config.rules.max_bet, Some(Rational64::new(501, 2)));
+
+        let config: Config = "[rules]\npenetration = 40".parse()?;
+        assert_eq!(
+            config.rules.cut_card,
+            Some(CutCard {
+                penetration: Penetration::Cards(40),
+                jitter: 0,
+            })
+        );
+
+        assert_eq!("".parse::<Config>()?, Config::default());
+        Ok(())
+    }
+
+    /// The line of a syntax error in the config.
+    fn bad_line(s: &str) -> Option<usize> {
+        match s.parse::<Config>() {
+            Err(BlJaError::ConfigSyntax { line, .. }) => Some(line),
+            _ => None,
+        }
+    }
+
+    /// The setting with an invalid value in the config.
+    fn bad_setting(s: &str) -> Option<String> {
+        match s.parse::<Config>() {
+            Err(BlJaError::ConfigValue { key, .. }) => Some(key),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(bad_line("decks = 2\nplayers"), Some(2));
+        assert_eq!(bad_line("[rules"), Some(1));
+        assert_eq!(bad_line("names = [\"Ann\""), Some(1));
+        assert_eq!(bad_line("surrender = late"), Some(1));
+        assert_eq!(bad_line("decks = 2\ndecks = 3"), Some(2));
+        assert_eq!(bad_line("decks = 2 3"), Some(1));
+        // Unterminated strings
+        assert_eq!(bad_line("[rules]\nsurrender = 'late"), Some(2));
+        assert_eq!(bad_line("[rules]\nsurrender = \"late"), Some(2));
+        // A multi-line string runs to the end of the file
+        assert_eq!(bad_line("count = \"\"\"Zen\n\ntrainer = true"), Some(3));
+        // Values of the wrong type
+        assert_eq!(bad_line("decks = \"6\""), Some(1));
+        assert_eq!(bad_line("players = 2.5"), Some(1));
+        assert_eq!(bad_line("names = [\"Ann\", 2]"), Some(1));
+        assert_eq!(bad_line("[rules]\ndealer_hits_soft_17 = 1"), Some(2));
+
+        let settings = [
+            ("decks = 9", "decks"),
+            ("bankroll = -5", "bankroll"),
+            ("names = [\"Ann\", \" \"]", "names"),
+            ("[rules]\nsurrender = \"always\"", "rules.surrender"),
+            (
+                "[rules]\nblackjack_payout = \"3-2\"",
+                "rules.blackjack_payout",
+            ),
+            ("[rules]\nmin_bet = 10\nmax_bet = 5", "rules.max_bet"),
+            ("[rules]\nmin_bet = 0.50", "rules.min_bet"),
+            ("bankroll = 5\n[rules]\nmin_bet = 10", "bankroll"),
+            ("players = 1\nnames = [\"Ann\", \"Bob\"]", "names"),
+            ("[rules]\npenetration = \"100%\"", "rules.penetration"),
+            ("[rules]\npenetration = true", "rules.penetration"),
+            ("[rules]\njitter = 5", "rules.jitter"),
+            ("[rules]\nburn = 11", "rules.burn"),
+            ("count = \"red seven\"", "count"),
+        ];
+        for (config, setting) in settings.iter() {
+            assert_eq!(bad_setting(config), Some(setting.to_string()), "{}", config);
+        }
+
+        // Unknown settings are named in the error
+        assert_eq!(bad_line("[limits]\nmin_bet = 5"), Some(1));
+        let unknown = "[rules]\nh17 = true"
+            .parse::<Config>()
+            .unwrap_err()
+            .to_string();
+        assert!(unknown.starts_with("Config error on line 2: unknown field `h17`"));
+    }
+
+    #[test]
+    fn toml_syntax() -> Res<()> {
+        // Dotted keys, inline tables and multi-line strings are all TOML
+        let dotted: Config = "rules.surrender = \"late\"\nrules.burn = 1".parse()?;
+        let inline: Config = "rules = { surrender = \"late\", burn = 1 }".parse()?;
+        assert_eq!(dotted, inline);
+        assert_eq!(dotted.rules.surrender, SurrenderRule::Late);
+        assert_eq!(dotted.rules.burn, 1);
+
+        let names: Config = "names = [\"\"\"\nAnn\"\"\", '''Bob''']".parse()?;
+        assert_eq!(
+            names.names,
+            Some(vec!["Ann".to_string(), "Bob".to_string()])
+        );
+        Ok(())
+    }
+}
@@ -2,3 +2,4 @@
 pub const TWENTYONE: usize = 21;
 pub const SPLITCARDCOUNT: usize = 2;
 pub const DOUBLECARDCOUNT: usize = 2;
+pub const STARTING_MONEY: i64 = 500;
@@ -0,0 +1,454 @@
+//! Card counting.  A `Counter` follows the cards dealt in the engine's events, keeping the running count
+//! of a counting system and the true count.  Face down cards, such as the dealer's hole card or a burned
+//! card, are taken out of the cards remaining when dealt, but only counted once they are turned over.
+//!
+//! In training mode the player is asked for the count now and then, and their `Accuracy` is kept.
+
+use crate::card::{Card, Denomination, Visible};
+use crate::engine::Event;
+use crate::errors::BlJaError;
+use crate::player::parse_money;
+use crate::table::Table;
+use num::{Rational64, Signed};
+use std::str::FromStr;
+
+const CARDS_IN_DECK: i64 = 52;
+
+/// The chance that the trainer asks for the count before a round.
+pub const QUIZ_CHANCE: f64 = 0.3;
+
+/// A card counting system, as the tag added to the count for each rank.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CountingSystem {
+    pub name: &'static str,
+    /// Tags for aces, then 2 through 9, then ten valued cards.
+    pub tags: [Rational64; 10],
+    /// Unbalanced systems start each shoe below zero, by this much for every deck after the first, so
+    /// that the count has the same meaning for any number of decks.
+    pub start_per_deck: i64,
+}
+
+const fn tag(tag: i64) -> Rational64 {
+    Rational64::new_raw(tag, 1)
+}
+
+const fn half(halves: i64) -> Rational64 {
+    Rational64::new_raw(halves, 2)
+}
+
+pub const HI_LO: CountingSystem = CountingSystem {
+    name: "Hi-Lo",
+    tags: [
+        tag(-1),
+        tag(1),
+        tag(1),
+        tag(1),
+        tag(1),
+        tag(1),
+        tag(0),
+        tag(0),
+        tag(0),
+        tag(-1),
+    ],
+    start_per_deck: 0,
+};
+
+pub const KO: CountingSystem = CountingSystem {
+    name: "KO",
+    tags: [
+        tag(-1),
+        tag(1),
+        tag(1),
+        tag(1),
+        tag(1),
+        tag(1),
+        tag(1),
+        tag(0),
+        tag(0),
+        tag(-1),
+    ],
+    start_per_deck: -4,
+};
+
+pub const OMEGA_II: CountingSystem = CountingSystem {
+    name: "Omega II",
+    tags: [
+        tag(0),
+        tag(1),
+        tag(1),
+        tag(2),
+        tag(2),
+        tag(2),
+        tag(1),
+        tag(0),
+        tag(-1),
+        tag(-2),
+    ],
+    start_per_deck: 0,
+};
+
+pub const ZEN: CountingSystem = CountingSystem {
+    name: "Zen",
+    tags: [
+        tag(-1),
+        tag(1),
+        tag(1),
+        tag(2),
+        tag(2),
+        tag(2),
+        tag(1),
+        tag(0),
+        tag(0),
+        tag(-2),
+    ],
+    start_per_deck: 0,
+};
+
+pub const WONG_HALVES: CountingSystem = CountingSystem {
+    name: "Wong Halves",
+    tags: [
+        tag(-1),
+        half(1),
+        tag(1),
+        tag(1),
+        half(3),
+        tag(1),
+        half(1),
+        tag(0),
+        half(-1),
+        tag(-1),
+    ],
+    start_per_deck: 0,
+};
+
+/// The counting systems that come with the game.
+pub const SYSTEMS: [CountingSystem; 5] = [HI_LO, KO, OMEGA_II, ZEN, WONG_HALVES];
+
+impl CountingSystem {
+    /// The tag added to the count when the card is seen.  Cards that are not played, such as the cut card,
+    /// are not counted.
+    pub fn tag(&self, card: &Card) -> Rational64 {
+        use Denomination::*;
+        match card.denom() {
+            Ace => self.tags[0],
+            Numerical(value @ 2..=9) => self.tags[value - 1],
+            King | Queen | Jack | Numerical(10) => self.tags[9],
+            Numerical(_) | Extra(_) => Rational64::from_integer(0),
+        }
+    }
+}
+
+/// Finds a system by name, ignoring case, spaces and dashes, so "hilo" and "Hi-Lo" are the same system.
+impl FromStr for CountingSystem {
+    type Err = BlJaError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let simple = |name: &str| -> String {
+            name.chars()
+                .filter(char::is_ascii_alphanumeric)
+                .map(|c| c.to_ascii_lowercase())
+                .collect()
+        };
+        SYSTEMS
+            .iter()
+            .find(|system| simple(system.name) == simple(s))
+            .copied()
+            .ok_or_else(|| BlJaError::UnknownCountingSystem(s.to_string()))
+    }
+}
+
+/// Keeps the count of a shoe as its cards are seen.
+#[derive(Debug, Clone)]
+pub struct Counter {
+    system: CountingSystem,
+    decks: usize,
+    running: Rational64,
+    // Cards known to be out of the shoe, seen or not
+    removed: usize,
+}
+
+impl Counter {
+    /// Starts counting the table's shoe, from the cards in its discard tray.
+    pub fn new(system: CountingSystem, table: &Table) -> Counter {
+        let mut counter = Counter {
+            system,
+            decks: table.decks(),
+            running: Rational64::from_integer(0),
+            removed: 0,
+        };
+        counter.reset();
+        for card in table.discards() {
+            counter.deal(card);
+        }
+        counter
+    }
+
+    #[inline]
+    pub fn system(&self) -> &CountingSystem {
+        &self.system
+    }
+
+    /// Starts the count over for a newly shuffled shoe.
+    pub fn reset(&mut self) {
+        self.running =
+            Rational64::from_integer(self.system.start_per_deck * (self.decks as i64 - 1));
+        self.removed = 0;
+    }
+
+    /// Starts the count over for a shoe made from the discard tray during a round.  The cards still in
+    /// play are not in it.
+    pub fn reshuffle_discards(&mut self, cards: usize) {
+        self.reset();
+        self.removed = (self.decks * CARDS_IN_DECK as usize).saturating_sub(cards);
+    }
+
+    /// Takes a card dealt from the shoe into account.  Face down cards wait to be counted by `reveal`.
+    pub fn deal(&mut self, card: &Visible<Card>) {
+        self.removed += 1;
+        if let Visible::FacedUp(card) = card {
+            self.running += self.system.tag(card);
+        }
+    }
+
+    /// Counts a face down card that has been turned over.
+    pub fn reveal(&mut self, card: &Card) {
+        self.running += self.system.tag(card);
+    }
+
+    /// Follows the cards in an event from the engine.
+    pub fn see(&mut self, event: &Event) {
+        match event {
+            Event::Shuffled => self.reset(),
+            Event::DiscardsShuffled(cards) => self.reshuffle_discards(*cards),
+            Event::Burned(card) | Event::DealerCard(card) | Event::PlayerCard { card, .. } => {
+                self.deal(card)
+            }
+            Event::HoleCardRevealed(card) => self.reveal(card),
+            _ => (),
+        }
+    }
+
+    #[inline]
+    pub fn running_count(&self) -> Rational64 {
+        self.running
+    }
+
+    /// The decks left in the shoe, as a counter would judge from the cards that have come out of it.
+    pub fn decks_remaining(&self) -> Rational64 {
+        let cards = self.decks as i64 * CARDS_IN_DECK - self.removed as i64;
+        Rational64::new(cards.max(0), CARDS_IN_DECK)
+    }
+
+    /// The running count for each deck remaining.  Less than half a deck is taken as half a deck, so the
+    /// true count stays sensible at the very end of a shoe.
+    pub fn true_count(&self) -> Rational64 {
+        self.running / self.decks_remaining().max(Rational64::new(1, 2))
+    }
+}
+
+/// How well the count has been kept when the trainer asked for it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Accuracy {
+    pub asked: usize,
+    pub running_right: usize,
+    pub true_right: usize,
+}
+
+impl Accuracy {
+    /// Checks the running and true count given against the counter's, returning whether each was right.
+    /// A true count is an estimate, so one within a point of the exact true count is right.
+    pub fn check(
+        &mut self,
+        counter: &Counter,
+        running: Rational64,
+        true_count: Rational64,
+    ) -> (bool, bool) {
+        let running_right = running == counter.running_count();
+        let true_right = (true_count - counter.true_count()).abs() < Rational64::from_integer(1);
+        self.asked += 1;
+        self.running_right += running_right as usize;
+        self.true_right += true_right as usize;
+        (running_right, true_right)
+    }
+}
+
+/// Reads a count as the player types it, such as "-3", "+2" or "1.5".
+pub fn parse_count(s: &str) -> Option<Rational64> {
+    let s = s.trim();
+    match s.strip_prefix('-') {
+        Some(count) => parse_money(count).ok().map(|count| -count),
+        None => parse_money(s.strip_prefix('+').unwrap_or(s)).ok(),
+    }
+}
+
+#[cfg(test)]
+mod countingtests {
+    use super::*;
+    use crate::card::Denomination::*;
+    use crate::card::Suit;
+    use crate::engine::{Command, Engine, Phase};
+    use crate::hand::Action;
+    use crate::player::Player;
+    use crate::rules::TableRules;
+    use crate::Res;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha12Rng;
+
+    fn up(denom: Denomination) -> Visible<Card> {
+        Visible::FacedUp(Card::new(denom, Suit::Spades))
+    }
+
+    fn table(decks: usize, rules: TableRules) -> Res<Table> {
+        Table::with_rng(
+            Player::seats(2, &[], 500.into()),
+            decks,
+            rules,
+            ChaCha12Rng::seed_from_u64(13),
+        )
+    }
+
+    #[test]
+    fn systems() -> Res<()> {
+        // Balanced systems count to zero over a whole deck
+        let deck = crate::deck::Deck::new(1)?;
+        for system in SYSTEMS.iter() {
+            let total: Rational64 = deck.iter().map(|card| system.tag(card)).sum();
+            let expected = if system.name == "KO" { 4 } else { 0 };
+            assert_eq!(total, expected.into(), "{}", system.name);
+        }
+
+        assert_eq!(
+            WONG_HALVES.tag(&Card::new(Numerical(5), Suit::Hearts)),
+            Rational64::new(3, 2)
+        );
+        assert_eq!(OMEGA_II.tag(&Card::new(Ace, Suit::Hearts)), 0.into());
+        assert_eq!(ZEN.tag(&Card::new(Queen, Suit::Hearts)), (-2).into());
+
+        assert_eq!("hilo".parse::<CountingSystem>()?, HI_LO);
+        assert_eq!("Omega-II".parse::<CountingSystem>()?, OMEGA_II);
+        assert_eq!("wong halves".parse::<CountingSystem>()?, WONG_HALVES);
+        assert_eq!(
+            "red seven".parse::<CountingSystem>().unwrap_err(),
+            BlJaError::UnknownCountingSystem("red seven".to_string())
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn counts() -> Res<()> {
+        let mut counter = Counter::new(HI_LO, &table(2, TableRules::default())?);
+        assert_eq!(counter.decks_remaining(), 2.into());
+        for card in [Numerical(2), Numerical(5), King, Numerical(6), Numerical(8)].iter() {
+            counter.deal(&up(*card));
+        }
+        assert_eq!(counter.running_count(), 2.into());
+
+        // The hole card is only counted once it is turned over
+        let hole = Card::new(Numerical(4), Suit::Clubs);
+        counter.see(&Event::DealerCard(Visible::FacedDown(hole)));
+        assert_eq!(counter.running_count(), 2.into());
+        counter.see(&Event::HoleCardRevealed(hole));
+        assert_eq!(counter.running_count(), 3.into());
+        assert_eq!(counter.decks_remaining(), Rational64::new(98, 52));
+        assert_eq!(counter.true_count(), Rational64::new(3 * 52, 98));
+
+        counter.see(&Event::Shuffled);
+        assert_eq!(counter.running_count(), 0.into());
+        assert_eq!(counter.decks_remaining(), 2.into());
+
+        // A shoe made from the discards during a round only holds those cards
+        counter.deal(&up(King));
+        counter.see(&Event::DiscardsShuffled(26));
+        assert_eq!(counter.running_count(), 0.into());
+        assert_eq!(counter.decks_remaining(), Rational64::new(1, 2));
+
+        // KO starts below zero in a shoe
+        let counter = Counter::new(KO, &table(6, TableRules::default())?);
+        assert_eq!(counter.running_count(), (-20).into());
+        Ok(())
+    }
+
+    #[test]
+    fn training() -> Res<()> {
+        assert_eq!(parse_count(" -3 "), Some((-3).into()));
+        assert_eq!(parse_count("+2"), Some(2.into()));
+        assert_eq!(parse_count("1.5"), Some(Rational64::new(3, 2)));
+        assert_eq!(parse_count("0"), Some(0.into()));
+        assert_eq!(parse_count("--1"), None);
+        assert_eq!(parse_count("lots"), None);
+
+        let mut counter = Counter::new(HI_LO, &table(2, TableRules::default())?);
+        for card in [Numerical(2), Numerical(3), Numerical(4)].iter() {
+            counter.deal(&up(*card));
+        }
+        // A running count of 3 with 101 cards left is a true count just over 1.5
+        let mut accuracy = Accuracy::default();
+        assert_eq!(accuracy.check(&counter, 3.into(), 1.into()), (true, true));
+        assert_eq!(accuracy.check(&counter, 3.into(), 2.into()), (true, true));
+        assert_eq!(accuracy.check(&counter, 2.into(), 3.into()), (false, false));
+        assert_eq!(
+            accuracy,
+            Accuracy {
+                asked: 3,
+                running_right: 2,
+                true_right: 2,
+            }
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn burned_cards() -> Res<()> {
+        let burn = |burn_face_up| TableRules {
+            burn: 2,
+            burn_face_up,
+            ..TableRules::default()
+        };
+
+        let hidden = table(1, burn(false))?;
+        let counter = Counter::new(HI_LO, &hidden);
+        assert_eq!(counter.running_count(), 0.into());
+        assert_eq!(counter.decks_remaining(), Rational64::new(50, 52));
+
+        let shown = table(1, burn(true))?;
+        let counter = Counter::new(HI_LO, &shown);
+        let expected: Rational64 = shown.discards().iter().map(|card| HI_LO.tag(card)).sum();
+        assert_eq!(counter.running_count(), expected);
+        assert_eq!(counter.decks_remaining(), Rational64::new(50, 52));
+        Ok(())
+    }
+
+    #[test]
+    fn follows_the_engine() -> Res<()> {
+        let mut engine = Engine::new(table(6, TableRules::default())?);
+        let mut counter = Counter::new(ZEN, engine.table());
+        let mut events = engine.start();
+        let mut rounds = 0;
+        while rounds < 5 {
+            for event in events.iter() {
+                counter.see(event);
+            }
+
+            // Every card is in the discard tray at the end of the round, face up
+            if events.contains(&Event::RoundOver) {
+                rounds += 1;
+                let table = engine.table();
+                let expected: Rational64 = table.discards().iter().map(|card| ZEN.tag(card)).sum();
+                assert_eq!(counter.running_count(), expected);
+                assert_eq!(
+                    counter.decks_remaining(),
+                    Rational64::new(table.cards_remaining() as i64, 52)
+                );
+            }
+
+            let command = match engine.phase() {
+                Phase::Betting { .. } => Command::Bet(10.into()),
+                Phase::Playing { .. } => Command::Act(Action::Stand),
+                Phase::Insurance { .. } => Command::Insure(0.into()),
+                _ => Command::Continue,
+            };
+            events = engine.handle(command)?;
+        }
+        Ok(())
+    }
+}
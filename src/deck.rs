@@ -5,10 +5,12 @@ use crate::card::{Card, Denomination, Denomination::*, Suit, Visible};
 use crate::errors::BlJaError;
 use std::error::Error;
 use std::ops::{Deref, DerefMut};
+use std::str::FromStr;
 
 const NUM_CARD_IN_DECK: usize = 52;
 const SUITS: [Suit; 4] = [Suit::Spades, Suit::Hearts, Suit::Clubs, Suit::Diamonds];
-const DEMONS: [Denomination; 13] = [
+/// The ranks in a deck, from the top of a new one.
+pub(crate) const DEMONS: [Denomination; 13] = [
     Ace,
     King,
     Queen,
@@ -31,6 +33,7 @@ const DEMONS: [Denomination; 13] = [
 /// a generic deck for any game.  Due to this, the generic methods such as shuffling and cutting
 /// which can be implemented by any type of deck for any game will be done by traits.
 #[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Deck {
     pub(crate) deck: Vec<Card>,
     pub(crate) reshuffle: bool,
@@ -101,6 +104,24 @@ impl Deck {
     }
 }
 
+/// Stacks a deck from cards separated by whitespace, such as "AS KD 5H".  The first card listed is dealt first.
+impl FromStr for Deck {
+    type Err = BlJaError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut deck = s
+            .split_whitespace()
+            .map(str::parse)
+            .collect::<Result<Vec<Card>, _>>()?;
+        // Cards are dealt from the back
+        deck.reverse();
+        Ok(Deck {
+            deck,
+            reshuffle: false,
+        })
+    }
+}
+
 #[cfg(test)]
 mod decktests {
     use super::*;
@@ -138,4 +159,26 @@ mod decktests {
 
         Ok(())
     }
+
+    #[test]
+    fn stacked() -> Result<(), Box<dyn Error>> {
+        let mut thedeck: Deck = "AS KD\n 5H".parse()?;
+        assert_eq!(thedeck.len(), 3);
+        assert_eq!(
+            thedeck.deal(true),
+            Visible::FacedUp(Card::new(Ace, Suit::Spades))
+        );
+        assert_eq!(
+            thedeck.deal(true),
+            Visible::FacedUp(Card::new(King, Suit::Diamonds))
+        );
+        assert_eq!(
+            thedeck.deal(false),
+            Visible::FacedDown(Card::new(Numerical(5), Suit::Hearts))
+        );
+
+        let err = "AS KX 5H".parse::<Deck>().unwrap_err();
+        assert_eq!(err, BlJaError::InvalidCard("KX".to_string()));
+        Ok(())
+    }
 }
@@ -5,7 +5,8 @@ use std::error::Error;
 
 /// Trait that the object can by shuffled
 pub trait Shufflable {
-    fn shuffle(&mut self);
+    /// Shuffles using the given random number generator.  A seeded generator gives the same order every time.
+    fn shuffle_with<R: Rng + ?Sized>(&mut self, rng: &mut R);
 }
 
 /// Trait that the object but by `cut` as in a deck of card.
@@ -14,11 +15,10 @@ pub trait Cuttable {
 }
 
 impl Shufflable for Deck {
-    fn shuffle(&mut self) {
+    fn shuffle_with<R: Rng + ?Sized>(&mut self, rng: &mut R) {
         // Requires using random numbers.
         // Start from the bottom of the deck, swap with one randomly located in the rest of the deck.
         // Move down the line, swapping cards at random with the i..vec.len() range.
-        let mut rng = rand::thread_rng();
         let len = self.len();
         for i in 0..(len - 2) {
             let other = rng.gen_range(i + 1..len);
@@ -51,7 +51,7 @@ mod trait_tests {
         assert_eq!(onedeck.get(0).unwrap(), &first);
         assert_eq!(onedeck.get(onedeck.len() - 1).unwrap(), &last);
 
-        onedeck.shuffle();
+        onedeck.shuffle_with(&mut rand::thread_rng());
         assert_ne!(onedeck, orig);
         assert_eq!(onedeck.len(), 52);
         let mut count = 0;
@@ -77,7 +77,7 @@ mod trait_tests {
         assert_eq!(sixdeck.get(0).unwrap(), &first);
         assert_eq!(sixdeck.get(sixdeck.len() - 1).unwrap(), &last);
 
-        sixdeck.shuffle();
+        sixdeck.shuffle_with(&mut rand::thread_rng());
         assert_ne!(sixdeck, orig);
         assert_eq!(sixdeck.len(), 312);
         let mut count = 0;
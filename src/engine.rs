@@ -0,0 +1,1521 @@
+//! The sequencing of a round of blackjack, free of any GUI code.  A front end drives the `Engine` by
+//! sending `Command`s, and renders the `Event`s that are returned.  The current `Phase` tells the front
+//! end which command the engine is waiting on.
+
+use crate::card::{Card, Visible};
+use crate::errors::BlJaError;
+use crate::hand::{Action, Hand, HandType};
+use crate::player::{Player, Status};
+use crate::rules::{DealingMode, SurrenderRule, TableRules};
+use crate::strategy;
+use crate::table::Table;
+use crate::Res;
+use num::{Rational64, Zero};
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::rc::Rc;
+
+/// Commands sent from a front end to the engine.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Command {
+    Bet(Rational64),
+    Insure(Rational64),
+    EvenMoney,
+    Act(Action),
+    Continue,
+}
+
+/// Where the engine is in the round, which determines the command it is waiting on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Phase {
+    /// The deck has been reshuffled.  Waiting on `Continue`.
+    Shuffled,
+    /// Waiting on a `Bet` from the player.
+    Betting { player: usize },
+    /// Waiting on the player to `Act` with a surrender before the dealer peeks, or `Continue` to decline.
+    Surrender { player: usize },
+    /// Waiting on an `Insure` from the player.
+    Insurance { player: usize },
+    /// The player has a natural against the dealer's ace.  Waiting on `EvenMoney` to take a 1:1 payout,
+    /// or `Continue` to decline.
+    EvenMoney { player: usize },
+    /// Waiting on an `Act` for the player's hand.
+    Playing { player: usize, hand: usize },
+    /// No more actions for the player's hand.  Waiting on `Continue`.
+    HandOver { player: usize, hand: usize },
+    /// The dealer has finished drawing.  Waiting on `Continue`.
+    DealerDone,
+    /// The player's hand has been paid out.  Waiting on `Continue`.
+    Settled { player: usize, hand: usize },
+    /// All players are out of money.  No commands are accepted.
+    GameOver,
+}
+
+/// Events produced by the engine for a front end to render, in the order they occurred.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Event {
+    Shuffled,
+    BetTurn(usize),
+    BetPlaced {
+        player: usize,
+        bet: Rational64,
+    },
+    PlayerCard {
+        player: usize,
+        hand: usize,
+        card: Visible<Card>,
+    },
+    DealerCard(Visible<Card>),
+    /// A card burned from the top of a new shoe into the discard tray.
+    Burned(Visible<Card>),
+    /// The shoe ran out during the round, so the discard tray was shuffled into a shoe of this many cards.
+    DiscardsShuffled(usize),
+    /// The player is offered early surrender.
+    SurrenderTurn(usize),
+    InsuranceTurn(usize),
+    InsurancePlaced {
+        player: usize,
+        bet: Rational64,
+    },
+    EvenMoneyTurn(usize),
+    EvenMoneyPaid {
+        player: usize,
+        payout: Rational64,
+    },
+    HoleCardRevealed(Card),
+    /// The dealer peeked under the face up ace or ten card and found a natural.
+    DealerNatural,
+    PlayTurn {
+        player: usize,
+        hand: usize,
+    },
+    Split {
+        player: usize,
+        hand: usize,
+    },
+    Doubled {
+        player: usize,
+        hand: usize,
+        bet: Rational64,
+    },
+    Stood {
+        player: usize,
+        hand: usize,
+    },
+    Surrendered {
+        player: usize,
+        hand: usize,
+        refund: Rational64,
+    },
+    /// The hand ran out of actions without the player standing (natural, bust, 21, doubled).
+    HandOver {
+        player: usize,
+        hand: usize,
+        hand_type: HandType,
+    },
+    DealerTurn,
+    Settled(Settlement),
+    RoundOver,
+    GameOver,
+}
+
+/// The result of a hand against the dealer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Outcome {
+    Bust,
+    Blackjack,
+    Win,
+    Push,
+    Lose,
+    Surrender,
+    EvenMoney,
+}
+
+/// How a single hand was paid out.  `payout` includes the returned bet.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Settlement {
+    pub player: usize,
+    pub hand: usize,
+    pub outcome: Outcome,
+    pub payout: Rational64,
+    pub insurance: Rational64,
+}
+
+/// Runs rounds of play at a table.
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Engine {
+    table: Table,
+    phase: Phase,
+}
+
+impl Engine {
+    pub fn new(table: Table) -> Engine {
+        Engine {
+            table,
+            phase: Phase::Shuffled,
+        }
+    }
+
+    #[inline]
+    pub fn table(&self) -> &Table {
+        &self.table
+    }
+
+    #[inline]
+    pub fn phase(&self) -> Phase {
+        self.phase
+    }
+
+    /// Starts the first round.  Later rounds are started by the engine after settlement.
+    pub fn start(&mut self) -> Vec<Event> {
+        let mut events = vec![];
+        self.start_round(&mut events);
+        events
+    }
+
+    /// Seats a new player, such as one with a saved profile, in place of the one there, returning who left.
+    /// Only allowed during betting, for seats that have not bet yet, and the player must cover the table
+    /// minimum.
+    pub fn seat(&mut self, seat: usize, player: Player) -> Res<Player> {
+        match self.phase {
+            Phase::Shuffled => (),
+            Phase::Betting { player: better } if seat >= better => (),
+            _ => {
+                return Err(Box::new(BlJaError::ImproperAction(
+                    "Players can only sit down before betting",
+                )))
+            }
+        }
+        if player.money() < self.table.rules().min_bet {
+            return Err(Box::new(BlJaError::NotEnoughMoney));
+        }
+        let theseat = self
+            .table
+            .player(seat)
+            .ok_or(BlJaError::InvalidIndex(seat))?;
+        Ok(theseat.replace(player))
+    }
+
+    /// Actions available to the hand currently being played.  Empty outside of the playing phase.
+    pub fn actions(&self) -> HashSet<Action> {
+        match self.phase {
+            Phase::Playing { player, hand } => self
+                .player(player)
+                .borrow()
+                .actions(hand, self.table.rules()),
+            _ => HashSet::with_capacity(0),
+        }
+    }
+
+    /// The basic strategy play for the hand currently being played.  `None` outside of the playing phase.
+    pub fn advice(&self) -> Option<Action> {
+        let (hand, up_card) = self.decision()?;
+        strategy::advise(&hand, &up_card, self.table.rules(), &self.actions())
+    }
+
+    /// The hand being played and the dealer's up card.  `None` outside of the playing phase.
+    pub fn decision(&self) -> Option<(Hand, Card)> {
+        match self.phase {
+            Phase::Playing { player, hand } => {
+                let up_card = **self.table.dealer.get_hand(0)?.card_iter().next()?;
+                Some((self.hand(player, hand), up_card))
+            }
+            _ => None,
+        }
+    }
+
+    /// Performs the command, returning what happened.  Errors if the command is not valid for the
+    /// current phase, or is rejected by the player (not enough money, excessive insurance, etc).
+    pub fn handle(&mut self, command: Command) -> Res<Vec<Event>> {
+        let mut events = vec![];
+        match (self.phase, command) {
+            (Phase::Betting { player }, Command::Bet(bet)) => self.bet(player, bet, &mut events)?,
+            (Phase::Surrender { player }, Command::Act(Action::Surrender)) => {
+                self.surrender(player, 0, &mut events)?;
+                self.offer_surrender(Some(player), &mut events)
+            }
+            (Phase::Surrender { player }, Command::Continue) => {
+                self.offer_surrender(Some(player), &mut events)
+            }
+            (Phase::Insurance { player }, Command::Insure(bet)) => {
+                self.insure(player, bet, &mut events)?
+            }
+            (Phase::EvenMoney { player }, Command::EvenMoney) => {
+                let payout = self.player(player).borrow_mut().take_even_money()?;
+                events.push(Event::EvenMoneyPaid { player, payout });
+                self.offer_insurance(Some(player), &mut events)
+            }
+            (Phase::EvenMoney { player }, Command::Continue) => {
+                self.offer_insurance(Some(player), &mut events)
+            }
+            (Phase::Playing { player, hand }, Command::Act(action)) => {
+                self.act(player, hand, action, &mut events)?
+            }
+            (Phase::Shuffled, Command::Continue) => self.start_betting(&mut events),
+            (Phase::HandOver { player, hand }, Command::Continue) => {
+                self.next_turn(player, hand, &mut events)
+            }
+            (Phase::DealerDone, Command::Continue) => {
+                let first = self.first_player().expect("A round needs a player");
+                self.settle(first, 0, &mut events)
+            }
+            (Phase::Settled { player, hand }, Command::Continue) => {
+                self.after_settle(player, hand, &mut events)
+            }
+            _ => {
+                return Err(Box::new(BlJaError::ImproperAction(
+                    "Command is not valid at this point of the round",
+                )))
+            }
+        }
+        Ok(events)
+    }
+
+    /// Begins a round.  Only players with money at this point in time will play.
+    fn start_round(&mut self, events: &mut Vec<Event>) {
+        if self.first_player().is_none() {
+            self.phase = Phase::GameOver;
+            events.push(Event::GameOver);
+            return;
+        }
+        if self.table.reshuffle {
+            self.table.shuffle();
+            events.push(Event::Shuffled);
+            events.extend(
+                self.table
+                    .discards()
+                    .iter()
+                    .map(|&card| Event::Burned(card)),
+            );
+            // Without a cut card the deck is reshuffled every round, so there is no need to pause for it.
+            if self.table.uses_cut_card() {
+                self.phase = Phase::Shuffled;
+                return;
+            }
+        }
+        self.start_betting(events)
+    }
+
+    fn start_betting(&mut self, events: &mut Vec<Event>) {
+        let first = self.first_player().expect("A round needs a player");
+        self.phase = Phase::Betting { player: first };
+        events.push(Event::BetTurn(first));
+    }
+
+    fn bet(&mut self, player: usize, bet: Rational64, events: &mut Vec<Event>) -> Res<()> {
+        self.player(player)
+            .borrow_mut()
+            .place_bet(bet, self.table.rules())?;
+        events.push(Event::BetPlaced { player, bet });
+
+        match self.next_player(player) {
+            Some(next) => {
+                self.phase = Phase::Betting { player: next };
+                events.push(Event::BetTurn(next));
+            }
+            None => self.deal(events),
+        }
+        Ok(())
+    }
+
+    /// Deals the initial cards, then offers early surrender and insurance.
+    fn deal(&mut self, events: &mut Vec<Event>) {
+        self.table.deal_players();
+        self.report_discards_shuffled(events);
+
+        for (index, player) in self.table.player_iter().enumerate() {
+            let player = player.borrow();
+            if player.status() != Status::Playing {
+                continue;
+            }
+            for card in player.get_hand(0).unwrap().card_iter() {
+                events.push(Event::PlayerCard {
+                    player: index,
+                    hand: 0,
+                    card: *card,
+                });
+            }
+        }
+        for card in self.table.dealer.get_hand(0).unwrap().card_iter() {
+            events.push(Event::DealerCard(*card));
+        }
+
+        self.offer_surrender(None, events)
+    }
+
+    /// Offers early surrender to the players after `after` when the dealer could have a natural.  Moves on
+    /// to insurance once every player has been asked.
+    fn offer_surrender(&mut self, after: Option<usize>, events: &mut Vec<Event>) {
+        let upcard = *self
+            .table
+            .dealer
+            .get_hand(0)
+            .unwrap()
+            .card_iter()
+            .next()
+            .unwrap();
+        if self.table.rules().surrender == SurrenderRule::Early
+            && (upcard.is_ace() || upcard.is_10card())
+        {
+            if let Some(next) =
+                self.next_player_where(after, |p| p.get_hand(0).unwrap().surrenderable())
+            {
+                self.phase = Phase::Surrender { player: next };
+                events.push(Event::SurrenderTurn(next));
+                return;
+            }
+        }
+        self.offer_insurance(None, events)
+    }
+
+    /// Offers insurance, or even money to players holding a natural, to the players after `after` if the
+    /// dealer shows an ace.  Then peeks.
+    fn offer_insurance(&mut self, after: Option<usize>, events: &mut Vec<Event>) {
+        if self.table.dealer.get_hand(0).unwrap().is_first_card_ace() {
+            if let Some(next) = self.next_player_where(after, |p| {
+                p.get_hand(0).unwrap().hand_type() != HandType::Surrendered
+            }) {
+                if self.hand(next, 0).hand_type() == HandType::Natural {
+                    self.phase = Phase::EvenMoney { player: next };
+                    events.push(Event::EvenMoneyTurn(next));
+                } else {
+                    self.phase = Phase::Insurance { player: next };
+                    events.push(Event::InsuranceTurn(next));
+                }
+                return;
+            }
+        }
+        self.peek_for_natural(events)
+    }
+
+    fn insure(&mut self, player: usize, bet: Rational64, events: &mut Vec<Event>) -> Res<()> {
+        self.player(player).borrow_mut().set_insurance(bet)?;
+        events.push(Event::InsurancePlaced { player, bet });
+        self.offer_insurance(Some(player), events);
+        Ok(())
+    }
+
+    fn surrender(&mut self, player: usize, hand: usize, events: &mut Vec<Event>) -> Res<()> {
+        let refund = self.player(player).borrow_mut().surrender(hand)?;
+        events.push(Event::Surrendered {
+            player,
+            hand,
+            refund,
+        });
+        Ok(())
+    }
+
+    /// Peeks under the dealer's card for a natural.  The round goes straight to settlement if found.  Without
+    /// a hole card there is nothing to peek at, so play always begins.
+    fn peek_for_natural(&mut self, events: &mut Vec<Event>) {
+        let first = self.first_player().expect("A round needs a player");
+        if self.table.dealer.get_hand(0).unwrap().peek_for_natural() {
+            self.reveal_hole_card(events);
+            events.push(Event::DealerNatural);
+            self.settle(first, 0, events);
+        } else {
+            self.start_hand(first, 0, events);
+        }
+    }
+
+    /// Begins play of a player's hand.  Hands without any available actions (naturals, split aces) are
+    /// over right away.
+    fn start_hand(&mut self, player: usize, hand: usize, events: &mut Vec<Event>) {
+        events.push(Event::PlayTurn { player, hand });
+        if self
+            .player(player)
+            .borrow()
+            .actions(hand, self.table.rules())
+            .is_empty()
+        {
+            self.end_hand(player, hand, events);
+        } else {
+            self.phase = Phase::Playing { player, hand };
+        }
+    }
+
+    fn end_hand(&mut self, player: usize, hand: usize, events: &mut Vec<Event>) {
+        let hand_type = self.hand(player, hand).hand_type();
+        events.push(Event::HandOver {
+            player,
+            hand,
+            hand_type,
+        });
+        self.phase = Phase::HandOver { player, hand };
+    }
+
+    fn act(
+        &mut self,
+        player: usize,
+        hand: usize,
+        action: Action,
+        events: &mut Vec<Event>,
+    ) -> Res<()> {
+        if !self
+            .player(player)
+            .borrow()
+            .actions(hand, self.table.rules())
+            .contains(&action)
+        {
+            return Err(Box::new(BlJaError::ImproperAction(
+                "Action is not available for this hand",
+            )));
+        }
+        let playerrc = Rc::clone(self.player(player));
+
+        match action {
+            Action::Hit => {
+                let card = self.deal_card(events);
+                playerrc
+                    .borrow_mut()
+                    .get_hand_mut(hand)
+                    .unwrap()
+                    .insert(card);
+                events.push(Event::PlayerCard { player, hand, card });
+
+                if playerrc
+                    .borrow()
+                    .actions(hand, self.table.rules())
+                    .is_empty()
+                {
+                    self.end_hand(player, hand, events);
+                }
+            }
+            Action::Stand => {
+                events.push(Event::Stood { player, hand });
+                self.phase = Phase::HandOver { player, hand };
+            }
+            Action::Surrender => {
+                self.surrender(player, hand, events)?;
+                self.phase = Phase::HandOver { player, hand };
+            }
+            Action::Split => {
+                // Checked before dealing, so a refused split leaves the shoe as it was
+                playerrc.borrow().check_split(hand, self.table.rules())?;
+                let card1 = self.deal_card(events);
+                let card2 = self.deal_card(events);
+                playerrc
+                    .borrow_mut()
+                    .split_hand(hand, card1, card2, self.table.rules())?;
+                events.push(Event::Split { player, hand });
+                events.push(Event::PlayerCard {
+                    player,
+                    hand,
+                    card: card1,
+                });
+                events.push(Event::PlayerCard {
+                    player,
+                    hand: hand + 1,
+                    card: card2,
+                });
+                self.start_hand(player, hand, events);
+            }
+            Action::Double => {
+                let card = {
+                    let mut theplayer = playerrc.borrow_mut();
+                    let bet = theplayer
+                        .get_hand(hand)
+                        .unwrap()
+                        .bet()
+                        .unwrap_or_else(Rational64::zero);
+                    // The hand accepts the double before the bet is taken, and the card is only dealt
+                    // once both are accepted
+                    let doubled = theplayer.get_hand(hand).unwrap().clone().double()?;
+                    theplayer.double(bet, self.table.rules())?;
+                    let card = self.deal_card(events);
+                    theplayer.replace_hand(hand, doubled.insert(card));
+                    events.push(Event::Doubled {
+                        player,
+                        hand,
+                        bet: bet * 2,
+                    });
+                    card
+                };
+                events.push(Event::PlayerCard { player, hand, card });
+                self.end_hand(player, hand, events);
+            }
+        }
+        Ok(())
+    }
+
+    /// Moves on to the player's next split hand, the next player, or the dealer.
+    fn next_turn(&mut self, player: usize, hand: usize, events: &mut Vec<Event>) {
+        if self.player(player).borrow().num_hands() > hand + 1 {
+            self.start_hand(player, hand + 1, events);
+        } else if let Some(next) = self.next_player(player) {
+            self.start_hand(next, 0, events);
+        } else {
+            self.dealer_play(events);
+        }
+    }
+
+    fn dealer_play(&mut self, events: &mut Vec<Event>) {
+        events.push(Event::DealerTurn);
+        self.reveal_hole_card(events);
+
+        while self
+            .table
+            .rules()
+            .dealer_hits(self.table.dealer.get_hand(0).unwrap())
+        {
+            let card = self.deal_card(events);
+            self.table.dealer.get_hand_mut(0).unwrap().insert(card);
+            events.push(Event::DealerCard(card));
+        }
+        self.phase = Phase::DealerDone;
+    }
+
+    /// Deals a card face up, first telling of the discards being shuffled if the shoe ran out.
+    fn deal_card(&mut self, events: &mut Vec<Event>) -> Visible<Card> {
+        let card = self.table.deal_card(true);
+        self.report_discards_shuffled(events);
+        card
+    }
+
+    fn report_discards_shuffled(&mut self, events: &mut Vec<Event>) {
+        if let Some(cards) = self.table.take_discards_shuffled() {
+            events.push(Event::DiscardsShuffled(cards));
+        }
+    }
+
+    fn reveal_hole_card(&mut self, events: &mut Vec<Event>) {
+        let hand = self.table.dealer_mut().get_hand_mut(0).unwrap();
+        if let Some(Visible::FacedDown(card)) = hand.card_iter().last().copied() {
+            hand.flip_over();
+            events.push(Event::HoleCardRevealed(card));
+        }
+    }
+
+    /// Pays out a player's hand against the dealer's.
+    fn settle(&mut self, player: usize, hand: usize, events: &mut Vec<Event>) {
+        let thehand = self.hand(player, hand);
+        let dealer = self.table.dealer.get_hand(0).unwrap().clone();
+        let rules = self.table.rules().clone();
+        let (outcome, mut payout) = settle_hand(&thehand, &dealer, &rules);
+        if outcome == Outcome::Lose
+            && dealer.hand_type() == HandType::Natural
+            && rules.dealing
+                == (DealingMode::NoHoleCard {
+                    original_bets_only: true,
+                })
+        {
+            payout = beyond_original_bet(&thehand, hand);
+        }
+
+        let insurance = {
+            let mut theplayer = self.player(player).borrow_mut();
+            // Insurance only pays when the dealer has a natural, and once however many hands were split
+            let insurance = match dealer.hand_type() {
+                HandType::Natural if hand == 0 => {
+                    theplayer.insurance() + theplayer.insurance() * rules.insurance_payout
+                }
+                _ => Rational64::zero(),
+            };
+            theplayer.collect(payout + insurance);
+            insurance
+        };
+
+        events.push(Event::Settled(Settlement {
+            player,
+            hand,
+            outcome,
+            payout,
+            insurance,
+        }));
+        self.phase = Phase::Settled { player, hand };
+    }
+
+    /// Clears the settled hand, then settles the next one or begins a new round.
+    fn after_settle(&mut self, player: usize, hand: usize, events: &mut Vec<Event>) {
+        let cards = self
+            .player(player)
+            .borrow_mut()
+            .reset_after_round(hand, self.table.rules());
+        self.table.discard(cards);
+
+        if self.player(player).borrow().num_hands() > hand + 1 {
+            self.settle(player, hand + 1, events);
+        } else if let Some(next) = self.next_player(player) {
+            self.settle(next, 0, events);
+        } else {
+            let rules = self.table.rules().clone();
+            let cards = self.table.dealer.reset_after_round(0, &rules);
+            self.table.discard(cards);
+            events.push(Event::RoundOver);
+            self.start_round(events);
+        }
+    }
+
+    #[inline]
+    fn player(&self, index: usize) -> &Rc<RefCell<Player>> {
+        self.table
+            .player(index)
+            .expect("Player index is out of range")
+    }
+
+    fn hand(&self, player: usize, hand: usize) -> Hand {
+        self.player(player)
+            .borrow()
+            .get_hand(hand)
+            .expect("Hand index is out of range")
+            .clone()
+    }
+
+    fn first_player(&self) -> Option<usize> {
+        self.next_player_where(None, |_| true)
+    }
+
+    fn next_player(&self, current: usize) -> Option<usize> {
+        self.next_player_where(Some(current), |_| true)
+    }
+
+    /// Finds the next playing player after `after` (or from the first player if `None`) that matches.
+    fn next_player_where<F>(&self, after: Option<usize>, matches: F) -> Option<usize>
+    where
+        F: Fn(&Player) -> bool,
+    {
+        self.table
+            .player_iter()
+            .enumerate()
+            .skip(after.map_or(0, |i| i + 1))
+            .find(|(_, p)| {
+                let p = p.borrow();
+                p.status() == Status::Playing && matches(&p)
+            })
+            .map(|(i, _)| i)
+    }
+}
+
+/// Compares a player's hand to the dealer's, returning the outcome and the amount paid back to the player.
+pub fn settle_hand(hand: &Hand, dealer: &Hand, rules: &TableRules) -> (Outcome, Rational64) {
+    let bet = hand.bet().unwrap_or_else(Rational64::zero);
+
+    match (hand.hand_type(), dealer.hand_type()) {
+        // The refund or even money was collected earlier in the round
+        (HandType::Surrendered, _) => (Outcome::Surrender, Rational64::zero()),
+        (HandType::EvenMoney, _) => (Outcome::EvenMoney, Rational64::zero()),
+        (HandType::Bust, _) => (Outcome::Bust, Rational64::zero()),
+        (HandType::Natural, HandType::Natural) => (Outcome::Push, bet),
+        (_, HandType::Natural) => (Outcome::Lose, Rational64::zero()),
+        (HandType::Natural, _) => (Outcome::Blackjack, bet + bet * rules.blackjack_payout),
+        (_, HandType::Bust) => (Outcome::Win, bet * 2),
+        _ if hand.score() > dealer.score() => (Outcome::Win, bet * 2),
+        _ if hand.score() == dealer.score() => (Outcome::Push, bet),
+        _ => (Outcome::Lose, Rational64::zero()),
+    }
+}
+
+/// The part of a hand's bet that was added to the player's original bet.  All split hands after the first
+/// are extra, as is the doubled part of a doubled hand.
+fn beyond_original_bet(hand: &Hand, index: usize) -> Rational64 {
+    let bet = hand.bet().unwrap_or_else(Rational64::zero);
+    match hand.hand_type() {
+        _ if index > 0 => bet,
+        HandType::Doubled | HandType::DoubledSoft => bet / 2,
+        _ => Rational64::zero(),
+    }
+}
+
+#[cfg(test)]
+mod enginetests {
+    use super::*;
+    use crate::card::{Denomination, Suit};
+    use crate::deck::Deck;
+    use Denomination::*;
+
+    /// Table with a single player and a deck that will deal the cards in order.
+    fn engine(cards: &[Denomination]) -> Engine {
+        engine_with(TableRules::default(), cards)
+    }
+
+    fn engine_with(rules: TableRules, cards: &[Denomination]) -> Engine {
+        let mut engine =
+            Engine::new(Table::new(Player::seats(1, &[], 500.into()), 2, rules).unwrap());
+        assert_eq!(engine.start(), vec![Event::BetTurn(0)]);
+        stack(&mut engine, cards);
+        engine
+    }
+
+    fn stack(engine: &mut Engine, cards: &[Denomination]) {
+        // Cards are dealt from the back of the deck
+        engine.table.deck = Deck {
+            deck: cards
+                .iter()
+                .rev()
+                .map(|d| Card::new(*d, Suit::Hearts))
+                .collect(),
+            reshuffle: false,
+        };
+    }
+
+    fn money(engine: &Engine) -> Rational64 {
+        engine.table.player(0).unwrap().borrow().money()
+    }
+
+    fn settlement(events: &[Event]) -> Settlement {
+        events
+            .iter()
+            .find_map(|e| match e {
+                Event::Settled(s) => Some(*s),
+                _ => None,
+            })
+            .expect("No settlement event")
+    }
+
+    #[test]
+    fn stand_and_push() -> Res<()> {
+        // Player: 10, 7. Dealer: 9, 8
+        let mut engine = engine(&[Numerical(10), Numerical(9), Numerical(7), Numerical(8)]);
+
+        let events = engine.handle(Command::Bet(100.into()))?;
+        assert_eq!(events.len(), 6);
+        assert_eq!(
+            events[1],
+            Event::PlayerCard {
+                player: 0,
+                hand: 0,
+                card: Visible::FacedUp(Card::new(Numerical(10), Suit::Hearts)),
+            }
+        );
+        assert_eq!(
+            events[4],
+            Event::DealerCard(Visible::FacedDown(Card::new(Numerical(8), Suit::Hearts)))
+        );
+        assert_eq!(engine.phase(), Phase::Playing { player: 0, hand: 0 });
+        assert_eq!(money(&engine), 400.into());
+
+        engine.handle(Command::Act(Action::Stand))?;
+        assert_eq!(engine.phase(), Phase::HandOver { player: 0, hand: 0 });
+
+        let events = engine.handle(Command::Continue)?;
+        assert_eq!(events[0], Event::DealerTurn);
+        assert_eq!(engine.phase(), Phase::DealerDone);
+
+        let events = engine.handle(Command::Continue)?;
+        let settled = settlement(&events);
+        assert_eq!(settled.outcome, Outcome::Push);
+        assert_eq!(settled.payout, 100.into());
+        assert_eq!(money(&engine), 500.into());
+
+        let events = engine.handle(Command::Continue)?;
+        assert_eq!(events[0], Event::RoundOver);
+        assert_eq!(engine.phase(), Phase::Betting { player: 0 });
+        Ok(())
+    }
+
+    #[test]
+    fn hit_and_bust() -> Res<()> {
+        // Player: 10, 6, then hits a king. Dealer: 9, 8
+        let mut engine = engine(&[
+            Numerical(10),
+            Numerical(9),
+            Numerical(6),
+            Numerical(8),
+            King,
+        ]);
+        engine.handle(Command::Bet(100.into()))?;
+
+        let events = engine.handle(Command::Act(Action::Hit))?;
+        assert_eq!(
+            events.last(),
+            Some(&Event::HandOver {
+                player: 0,
+                hand: 0,
+                hand_type: HandType::Bust,
+            })
+        );
+
+        engine.handle(Command::Continue)?;
+        let events = engine.handle(Command::Continue)?;
+        assert_eq!(settlement(&events).outcome, Outcome::Bust);
+        assert_eq!(money(&engine), 400.into());
+        Ok(())
+    }
+
+    #[test]
+    fn natural_pays_three_to_two() -> Res<()> {
+        // Player: A, K. Dealer: 10, 7
+        let mut engine = engine(&[Ace, Numerical(10), King, Numerical(7)]);
+        engine.handle(Command::Bet(100.into()))?;
+        assert_eq!(engine.phase(), Phase::HandOver { player: 0, hand: 0 });
+
+        engine.handle(Command::Continue)?;
+        let events = engine.handle(Command::Continue)?;
+        assert_eq!(settlement(&events).outcome, Outcome::Blackjack);
+        assert_eq!(money(&engine), 650.into());
+        Ok(())
+    }
+
+    #[test]
+    fn blackjack_payouts() -> Res<()> {
+        let hand = |cards: &[Denomination]| {
+            let mut hand = Hand::new();
+            hand.set_bet(25.into());
+            for denom in cards {
+                hand.insert(Visible::FacedUp(Card::new(*denom, Suit::Clubs)));
+            }
+            hand
+        };
+        let natural = hand(&[Ace, King]);
+        let seventeen = hand(&[Numerical(10), Numerical(7)]);
+        let bust = hand(&[Numerical(10), Numerical(6), Queen]);
+
+        let payouts = [
+            (Rational64::new(3, 2), Rational64::new(125, 2)),
+            (Rational64::new(6, 5), Rational64::from_integer(55)),
+            (Rational64::from_integer(1), Rational64::from_integer(50)),
+        ];
+        for (ratio, payout) in payouts.iter() {
+            let rules = TableRules {
+                blackjack_payout: *ratio,
+                ..TableRules::default()
+            };
+            for dealer in [&seventeen, &bust].iter() {
+                assert_eq!(
+                    settle_hand(&natural, dealer, &rules),
+                    (Outcome::Blackjack, *payout)
+                );
+            }
+            // Only a natural is paid at the ratio
+            assert_eq!(
+                settle_hand(&natural, &natural, &rules),
+                (Outcome::Push, 25.into())
+            );
+            assert_eq!(
+                settle_hand(
+                    &hand(&[Numerical(5), Numerical(6), Queen]),
+                    &seventeen,
+                    &rules
+                ),
+                (Outcome::Win, 50.into())
+            );
+        }
+
+        // Through a round at a six to five table
+        let rules = TableRules {
+            blackjack_payout: Rational64::new(6, 5),
+            ..TableRules::default()
+        };
+        let mut engine = engine_with(rules, &[Ace, Numerical(10), King, Numerical(7)]);
+        engine.handle(Command::Bet(100.into()))?;
+        finish_round(&mut engine)?;
+        assert_eq!(money(&engine), 620.into());
+        Ok(())
+    }
+
+    #[test]
+    fn dealer_natural_with_insurance() -> Res<()> {
+        // Player: 10, 9. Dealer: A, K
+        let mut engine = engine(&[Numerical(10), Ace, Numerical(9), King]);
+        let events = engine.handle(Command::Bet(100.into()))?;
+        assert_eq!(events.last(), Some(&Event::InsuranceTurn(0)));
+
+        // Not a valid command during insurance
+        assert!(engine.handle(Command::Bet(10.into())).is_err());
+
+        let events = engine.handle(Command::Insure(50.into()))?;
+        assert!(events.contains(&Event::DealerNatural));
+        let settled = settlement(&events);
+        assert_eq!(settled.outcome, Outcome::Lose);
+        assert_eq!(settled.insurance, 150.into());
+        assert_eq!(engine.phase(), Phase::Settled { player: 0, hand: 0 });
+        assert_eq!(money(&engine), 500.into());
+        Ok(())
+    }
+
+    #[test]
+    fn split_plays_each_hand() -> Res<()> {
+        // Player: 8, 8, split receiving 3 and 10. Dealer: 10, 9.  First hand doubles and draws a 10.
+        let mut engine = engine(&[
+            Numerical(8),
+            Numerical(10),
+            Numerical(8),
+            Numerical(9),
+            Numerical(3),
+            Numerical(10),
+            Numerical(10),
+        ]);
+        engine.handle(Command::Bet(100.into()))?;
+        assert!(engine.actions().contains(&Action::Split));
+
+        engine.handle(Command::Act(Action::Split))?;
+        assert_eq!(engine.phase(), Phase::Playing { player: 0, hand: 0 });
+        assert_eq!(money(&engine), 300.into());
+
+        engine.handle(Command::Act(Action::Double))?;
+        assert_eq!(engine.phase(), Phase::HandOver { player: 0, hand: 0 });
+        assert_eq!(money(&engine), 200.into());
+
+        engine.handle(Command::Continue)?;
+        assert_eq!(engine.phase(), Phase::Playing { player: 0, hand: 1 });
+        engine.handle(Command::Act(Action::Stand))?;
+        engine.handle(Command::Continue)?;
+
+        // 21 wins the doubled bet, 18 loses to the dealer's 19
+        let events = engine.handle(Command::Continue)?;
+        assert_eq!(settlement(&events).payout, 400.into());
+        let events = engine.handle(Command::Continue)?;
+        assert_eq!(settlement(&events).outcome, Outcome::Lose);
+        assert_eq!(money(&engine), 600.into());
+        Ok(())
+    }
+
+    #[test]
+    fn resplit_aces() -> Res<()> {
+        // Player: A, A, resplits the first ace receiving another ace, then gets 9 and 5. Dealer: 10, 7
+        let rules = TableRules {
+            resplit_aces: true,
+            max_hands: 3,
+            ..TableRules::default()
+        };
+        let mut engine = engine_with(
+            rules,
+            &[
+                Ace,
+                Numerical(10),
+                Ace,
+                Numerical(7),
+                Ace,
+                Numerical(5),
+                Ace,
+                Numerical(9),
+            ],
+        );
+        engine.handle(Command::Bet(100.into()))?;
+        engine.handle(Command::Act(Action::Split))?;
+        let actions = engine.actions();
+        assert_eq!(actions.len(), 2);
+        assert!(actions.contains(&Action::Split));
+
+        engine.handle(Command::Act(Action::Split))?;
+        assert_eq!(engine.player(0).borrow().num_hands(), 3);
+        assert_eq!(money(&engine), 200.into());
+
+        // The table's limit of three hands stops another split
+        let actions = engine.actions();
+        assert_eq!(actions.len(), 1);
+        assert!(actions.contains(&Action::Stand));
+        engine.handle(Command::Act(Action::Stand))?;
+
+        // A, A loses, A, 9 wins, and A, 5 loses to the dealer's 17
+        let settlements = finish_round(&mut engine)?;
+        assert_eq!(settlements.len(), 3);
+        assert_eq!(settlements[1].outcome, Outcome::Win);
+        assert_eq!(money(&engine), 400.into());
+        Ok(())
+    }
+
+    #[test]
+    fn seating() -> Res<()> {
+        let mut engine = Engine::new(Table::new(
+            Player::seats(2, &[], 500.into()),
+            2,
+            TableRules::default(),
+        )?);
+        engine.start();
+        let left = engine.seat(1, Player::with_money("Ann".to_string(), 1000.into()))?;
+        assert_eq!(left.name(), "Player 2");
+        engine.handle(Command::Bet(10.into()))?;
+
+        // The first seat has already bet, and broke players cannot sit down
+        assert!(engine.seat(0, Player::new("Bob".to_string())).is_err());
+        assert!(engine
+            .seat(1, Player::with_money("Bob".to_string(), 0.into()))
+            .is_err());
+
+        engine.handle(Command::Bet(20.into()))?;
+        assert_eq!(engine.player(1).borrow().name(), "Ann");
+        assert_eq!(engine.player(1).borrow().money(), 980.into());
+        assert!(engine.seat(1, Player::new("Bob".to_string())).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn bet_limits() -> Res<()> {
+        // Player: 10, 6. Dealer: 10, 7, both rounds
+        let rules = TableRules {
+            min_bet: 200.into(),
+            max_bet: Some(400.into()),
+            ..TableRules::default()
+        };
+        let mut engine = engine_with(
+            rules,
+            &[Numerical(10), Numerical(10), Numerical(6), Numerical(7)],
+        );
+        for bet in [0, 150, 450].iter() {
+            assert!(engine.handle(Command::Bet((*bet).into())).is_err());
+            assert_eq!(engine.phase(), Phase::Betting { player: 0 });
+        }
+
+        // 300 left after losing 200 covers another round, 100 after that does not
+        engine.handle(Command::Bet(200.into()))?;
+        engine.handle(Command::Act(Action::Stand))?;
+        finish_round(&mut engine)?;
+        assert_eq!(engine.phase(), Phase::Betting { player: 0 });
+
+        stack(
+            &mut engine,
+            &[Numerical(10), Numerical(10), Numerical(6), Numerical(7)],
+        );
+        engine.handle(Command::Bet(200.into()))?;
+        engine.handle(Command::Act(Action::Stand))?;
+        finish_round(&mut engine)?;
+        assert_eq!(money(&engine), 100.into());
+        assert_eq!(engine.phase(), Phase::GameOver);
+        Ok(())
+    }
+
+    #[test]
+    fn seeded_rounds() -> Res<()> {
+        use rand::SeedableRng;
+        use rand_chacha::ChaCha12Rng;
+
+        // Every player stands, so the same seed should give the same events round after round
+        let play = |seed: u64| -> Res<Vec<Event>> {
+            let table = Table::with_rng(
+                Player::seats(3, &[], 500.into()),
+                6,
+                TableRules::default(),
+                ChaCha12Rng::seed_from_u64(seed),
+            )?;
+            let mut engine = Engine::new(table);
+            let mut events = engine.start();
+            for _ in 0..200 {
+                let command = match engine.phase() {
+                    Phase::Betting { .. } => Command::Bet(10.into()),
+                    Phase::Insurance { .. } => Command::Insure(0.into()),
+                    Phase::Playing { .. } => Command::Act(Action::Stand),
+                    Phase::GameOver => break,
+                    _ => Command::Continue,
+                };
+                events.extend(engine.handle(command)?);
+            }
+            Ok(events)
+        };
+        assert_eq!(play(7)?, play(7)?);
+        assert_ne!(play(7)?, play(8)?);
+        Ok(())
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_mid_round() -> Res<()> {
+        // Player: 10, 6. Dealer: 10, 7
+        let mut engine = engine(&[Numerical(10), Numerical(10), Numerical(6), Numerical(7)]);
+        engine.handle(Command::Bet(Rational64::new(201, 2)))?;
+        assert_eq!(engine.phase(), Phase::Playing { player: 0, hand: 0 });
+
+        // Money is saved as an exact ratio
+        let json = serde_json::to_string(&engine)?;
+        assert!(json.contains("[799,2]"));
+        let mut loaded: Engine = serde_json::from_str(&json)?;
+        assert_eq!(loaded.phase(), engine.phase());
+        assert_eq!(
+            loaded.handle(Command::Act(Action::Stand))?,
+            engine.handle(Command::Act(Action::Stand))?
+        );
+        assert_eq!(finish_round(&mut loaded)?, finish_round(&mut engine)?);
+        assert_eq!(money(&loaded), money(&engine));
+        Ok(())
+    }
+
+    #[test]
+    fn game_over() -> Res<()> {
+        // Player: 10, 6, then hits a king. Dealer: 10, 7
+        let mut engine = engine(&[
+            Numerical(10),
+            Numerical(10),
+            Numerical(6),
+            Numerical(7),
+            King,
+        ]);
+        engine.handle(Command::Bet(500.into()))?;
+        engine.handle(Command::Act(Action::Hit))?;
+        engine.handle(Command::Continue)?;
+        engine.handle(Command::Continue)?;
+
+        let events = engine.handle(Command::Continue)?;
+        assert_eq!(events, vec![Event::RoundOver, Event::GameOver]);
+        assert_eq!(engine.phase(), Phase::GameOver);
+        assert!(engine.handle(Command::Continue).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn dealer_soft_17() -> Res<()> {
+        // Player: 10, 9. Dealer: 6, A, then a 2 if the dealer hits soft 17
+        let cards = [Numerical(10), Numerical(6), Numerical(9), Ace, Numerical(2)];
+
+        let mut engine = engine(&cards);
+        engine.handle(Command::Bet(100.into()))?;
+        engine.handle(Command::Act(Action::Stand))?;
+        let events = engine.handle(Command::Continue)?;
+        assert!(!events.iter().any(|e| matches!(e, Event::DealerCard(_))));
+        assert_eq!(engine.table.dealer.score(0), 17);
+        let events = engine.handle(Command::Continue)?;
+        assert_eq!(settlement(&events).outcome, Outcome::Win);
+
+        let rules = TableRules {
+            dealer_hits_soft_17: true,
+            ..TableRules::default()
+        };
+        let mut engine = engine_with(rules, &cards);
+        engine.handle(Command::Bet(100.into()))?;
+        engine.handle(Command::Act(Action::Stand))?;
+        let events = engine.handle(Command::Continue)?;
+        assert_eq!(
+            events.last(),
+            Some(&Event::DealerCard(Visible::FacedUp(Card::new(
+                Numerical(2),
+                Suit::Hearts
+            ))))
+        );
+        assert_eq!(engine.table.dealer.score(0), 19);
+        let events = engine.handle(Command::Continue)?;
+        assert_eq!(settlement(&events).outcome, Outcome::Push);
+        Ok(())
+    }
+
+    #[test]
+    fn late_surrender() -> Res<()> {
+        // Player: 10, 6. Dealer: 10, 7
+        let cards = [Numerical(10), Numerical(10), Numerical(6), Numerical(7)];
+
+        // Not offered without the rule
+        let mut engine = engine(&cards);
+        engine.handle(Command::Bet(100.into()))?;
+        assert!(!engine.actions().contains(&Action::Surrender));
+        assert!(engine.handle(Command::Act(Action::Surrender)).is_err());
+
+        let rules = TableRules {
+            surrender: SurrenderRule::Late,
+            ..TableRules::default()
+        };
+        let mut engine = engine_with(rules, &cards);
+        engine.handle(Command::Bet(100.into()))?;
+        assert!(engine.actions().contains(&Action::Surrender));
+
+        let events = engine.handle(Command::Act(Action::Surrender))?;
+        assert_eq!(
+            events,
+            vec![Event::Surrendered {
+                player: 0,
+                hand: 0,
+                refund: 50.into(),
+            }]
+        );
+        assert_eq!(engine.phase(), Phase::HandOver { player: 0, hand: 0 });
+        assert_eq!(money(&engine), 450.into());
+
+        engine.handle(Command::Continue)?;
+        let events = engine.handle(Command::Continue)?;
+        assert_eq!(settlement(&events).outcome, Outcome::Surrender);
+        assert_eq!(money(&engine), 450.into());
+
+        Ok(())
+    }
+
+    #[test]
+    fn early_surrender() -> Res<()> {
+        // Player: 10, 6. Dealer: A, K
+        let cards = [Numerical(10), Ace, Numerical(6), King];
+        let rules = TableRules {
+            surrender: SurrenderRule::Early,
+            ..TableRules::default()
+        };
+
+        // Surrendering before the peek keeps half the bet against the dealer's natural, and skips insurance
+        let mut engine = engine_with(rules.clone(), &cards);
+        let events = engine.handle(Command::Bet(100.into()))?;
+        assert_eq!(events.last(), Some(&Event::SurrenderTurn(0)));
+        assert_eq!(engine.phase(), Phase::Surrender { player: 0 });
+        let events = engine.handle(Command::Act(Action::Surrender))?;
+        assert!(events.contains(&Event::DealerNatural));
+        assert_eq!(settlement(&events).outcome, Outcome::Surrender);
+        assert_eq!(money(&engine), 450.into());
+
+        // Declining goes on to insurance
+        let mut engine = engine_with(rules, &cards);
+        engine.handle(Command::Bet(100.into()))?;
+        let events = engine.handle(Command::Continue)?;
+        assert_eq!(events, vec![Event::InsuranceTurn(0)]);
+        let events = engine.handle(Command::Insure(0.into()))?;
+        assert_eq!(settlement(&events).outcome, Outcome::Lose);
+        assert_eq!(money(&engine), 400.into());
+        Ok(())
+    }
+
+    #[test]
+    fn even_money() -> Res<()> {
+        // Player: A, K. Dealer: A, 7 then A, K
+        for hole in [Numerical(7), King].iter() {
+            let mut engine = engine(&[Ace, Ace, King, *hole]);
+            let events = engine.handle(Command::Bet(100.into()))?;
+            assert_eq!(events.last(), Some(&Event::EvenMoneyTurn(0)));
+            assert!(engine.handle(Command::Insure(50.into())).is_err());
+
+            let events = engine.handle(Command::EvenMoney)?;
+            assert_eq!(
+                events[0],
+                Event::EvenMoneyPaid {
+                    player: 0,
+                    payout: 200.into(),
+                }
+            );
+            assert_eq!(money(&engine), 600.into());
+
+            // The hand is already paid, so settlement skips it
+            let mut events = events;
+            while !matches!(engine.phase(), Phase::Settled { .. }) {
+                events = engine.handle(Command::Continue)?;
+            }
+            let settled = settlement(&events);
+            assert_eq!(settled.outcome, Outcome::EvenMoney);
+            assert_eq!(settled.payout, 0.into());
+            assert_eq!(money(&engine), 600.into());
+        }
+
+        // Declining against the dealer's natural is a push
+        let mut engine = engine(&[Ace, Ace, King, King]);
+        engine.handle(Command::Bet(100.into()))?;
+        let events = engine.handle(Command::Continue)?;
+        assert_eq!(settlement(&events).outcome, Outcome::Push);
+        assert_eq!(money(&engine), 500.into());
+        Ok(())
+    }
+
+    #[test]
+    fn insurance_with_even_money() -> Res<()> {
+        // Player 1: A, K. Player 2: 10, 9. Dealer: A, 7
+        let mut engine = Engine::new(Table::new(
+            Player::seats(2, &[], 500.into()),
+            2,
+            TableRules::default(),
+        )?);
+        engine.start();
+        stack(
+            &mut engine,
+            &[Ace, Numerical(10), Ace, King, Numerical(9), Numerical(7)],
+        );
+        engine.handle(Command::Bet(100.into()))?;
+        let events = engine.handle(Command::Bet(100.into()))?;
+        assert_eq!(events.last(), Some(&Event::EvenMoneyTurn(0)));
+
+        let events = engine.handle(Command::Continue)?;
+        assert_eq!(events, vec![Event::InsuranceTurn(1)]);
+        let events = engine.handle(Command::Insure(50.into()))?;
+        assert_eq!(
+            events.last(),
+            Some(&Event::HandOver {
+                player: 0,
+                hand: 0,
+                hand_type: HandType::Natural,
+            })
+        );
+        assert_eq!(engine.table.player(1).unwrap().borrow().money(), 350.into());
+        Ok(())
+    }
+
+    /// Continues to the end of the round, returning the settlements.
+    fn finish_round(engine: &mut Engine) -> Res<Vec<Settlement>> {
+        let mut settlements = vec![];
+        while !matches!(engine.phase(), Phase::Betting { .. } | Phase::GameOver) {
+            for event in engine.handle(Command::Continue)? {
+                if let Event::Settled(s) = event {
+                    settlements.push(s);
+                }
+            }
+        }
+        Ok(settlements)
+    }
+
+    #[test]
+    fn no_hole_card_deal() -> Res<()> {
+        // Player: 10, 9. Dealer: A, then a K once the players finish
+        let rules = TableRules {
+            dealing: DealingMode::NoHoleCard {
+                original_bets_only: false,
+            },
+            ..TableRules::default()
+        };
+        let mut engine = engine_with(rules, &[Numerical(10), Ace, Numerical(9), King]);
+        let events = engine.handle(Command::Bet(100.into()))?;
+        let dealt: Vec<_> = events
+            .iter()
+            .filter(|e| matches!(e, Event::DealerCard(_)))
+            .collect();
+        assert_eq!(dealt.len(), 1);
+        assert_eq!(engine.table.dealer.get_hand(0).unwrap().num_cards(), 1);
+        assert!(!engine.table.dealer.get_hand(0).unwrap().peek_for_natural());
+
+        // Insurance is still offered, but there is nothing to peek at
+        assert_eq!(events.last(), Some(&Event::InsuranceTurn(0)));
+        let events = engine.handle(Command::Insure(50.into()))?;
+        assert!(!events.contains(&Event::DealerNatural));
+        assert_eq!(engine.phase(), Phase::Playing { player: 0, hand: 0 });
+
+        // The dealer's second card comes after the players, and is not a hole card
+        engine.handle(Command::Act(Action::Stand))?;
+        let events = engine.handle(Command::Continue)?;
+        assert!(!events
+            .iter()
+            .any(|e| matches!(e, Event::HoleCardRevealed(_))));
+        assert!(engine.table.dealer.get_hand(0).unwrap().peek_for_natural());
+        assert_eq!(
+            engine.table.dealer.get_hand(0).unwrap().hand_type(),
+            HandType::Natural
+        );
+
+        let settlements = finish_round(&mut engine)?;
+        assert_eq!(settlements[0].outcome, Outcome::Lose);
+        assert_eq!(settlements[0].insurance, 150.into());
+        assert_eq!(money(&engine), 500.into());
+        Ok(())
+    }
+
+    #[test]
+    fn no_hole_card_natural_takes_doubles() -> Res<()> {
+        // Player: 5, 6, doubles and draws a 10. Dealer: 10, then an A
+        let cards = [
+            Numerical(5),
+            Numerical(10),
+            Numerical(6),
+            Numerical(10),
+            Ace,
+        ];
+
+        for (original_bets_only, money_after) in [(false, 300), (true, 400)].iter() {
+            let rules = TableRules {
+                dealing: DealingMode::NoHoleCard {
+                    original_bets_only: *original_bets_only,
+                },
+                ..TableRules::default()
+            };
+            let mut engine = engine_with(rules, &cards);
+            engine.handle(Command::Bet(100.into()))?;
+            engine.handle(Command::Act(Action::Double))?;
+            assert_eq!(money(&engine), 300.into());
+
+            let settlements = finish_round(&mut engine)?;
+            assert_eq!(settlements[0].outcome, Outcome::Lose);
+            assert_eq!(money(&engine), (*money_after).into());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn no_hole_card_natural_takes_splits() -> Res<()> {
+        // Player: 8, 8, split receiving 3 and 10. Dealer: A, then a K
+        let cards = [
+            Numerical(8),
+            Ace,
+            Numerical(8),
+            Numerical(3),
+            Numerical(10),
+            King,
+        ];
+
+        for (original_bets_only, money_after) in [(false, 300), (true, 400)].iter() {
+            let rules = TableRules {
+                dealing: DealingMode::NoHoleCard {
+                    original_bets_only: *original_bets_only,
+                },
+                ..TableRules::default()
+            };
+            let mut engine = engine_with(rules, &cards);
+            engine.handle(Command::Bet(100.into()))?;
+            engine.handle(Command::Insure(0.into()))?;
+            engine.handle(Command::Act(Action::Split))?;
+            engine.handle(Command::Act(Action::Stand))?;
+            engine.handle(Command::Continue)?;
+            engine.handle(Command::Act(Action::Stand))?;
+            assert_eq!(money(&engine), 300.into());
+
+            let settlements = finish_round(&mut engine)?;
+            assert_eq!(settlements.len(), 2);
+            assert_eq!(money(&engine), (*money_after).into());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn no_hole_card_insurance_after_split() -> Res<()> {
+        // Player: 8, 8, insures, then splits receiving 3 and 10. Dealer: A, then a K
+        let rules = TableRules {
+            dealing: DealingMode::NoHoleCard {
+                original_bets_only: false,
+            },
+            ..TableRules::default()
+        };
+        let mut engine = engine_with(
+            rules,
+            &[
+                Numerical(8),
+                Ace,
+                Numerical(8),
+                Numerical(3),
+                Numerical(10),
+                King,
+            ],
+        );
+        engine.handle(Command::Bet(100.into()))?;
+        engine.handle(Command::Insure(50.into()))?;
+        engine.handle(Command::Act(Action::Split))?;
+        engine.handle(Command::Act(Action::Stand))?;
+        engine.handle(Command::Continue)?;
+        engine.handle(Command::Act(Action::Stand))?;
+        assert_eq!(money(&engine), 250.into());
+
+        // Both hands lose, and the insurance is paid once
+        let settlements = finish_round(&mut engine)?;
+        assert_eq!(settlements.len(), 2);
+        assert_eq!(settlements[0].insurance, 150.into());
+        assert_eq!(settlements[1].insurance, 0.into());
+        assert_eq!(money(&engine), 400.into());
+        Ok(())
+    }
+
+    #[test]
+    fn refused_actions_keep_the_shoe() -> Res<()> {
+        // Player: 8, 8 with 300 of 500 bet, so there is not enough to split or double. Dealer: 6, 10
+        let mut engine = engine(&[
+            Numerical(8),
+            Numerical(6),
+            Numerical(8),
+            Numerical(10),
+            Numerical(3),
+        ]);
+        engine.handle(Command::Bet(300.into()))?;
+        let cards = engine.table.deck.len();
+        for action in [Action::Double, Action::Split].iter() {
+            assert!(engine.handle(Command::Act(*action)).is_err());
+            assert_eq!(engine.table.deck.len(), cards);
+            assert_eq!(money(&engine), 200.into());
+            assert_eq!(engine.phase(), Phase::Playing { player: 0, hand: 0 });
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn out_of_cards_mid_round() -> Res<()> {
+        // Player: 10, 9 and stands. Dealer: 6, 10, then the stacked deck runs out
+        let mut engine = engine(&[Numerical(10), Numerical(6), Numerical(9), Numerical(10)]);
+        engine.handle(Command::Bet(100.into()))?;
+        engine.handle(Command::Act(Action::Stand))?;
+        let events = engine.handle(Command::Continue)?;
+        let shuffled = events
+            .iter()
+            .position(|e| *e == Event::DiscardsShuffled(104))
+            .expect("The shoe is replaced");
+        assert!(matches!(events[shuffled + 1], Event::DealerCard(_)));
+        assert!(!events.contains(&Event::Shuffled));
+        assert!(engine.table.reshuffle);
+        Ok(())
+    }
+}
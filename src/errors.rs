@@ -1,3 +1,5 @@
+use crate::player::format_money;
+use num::Rational64;
 use std::error::Error;
 use std::fmt::{Display, Formatter};
 
@@ -8,6 +10,25 @@ pub enum BlJaError {
     ImproperAction(&'static str),
     NotEnoughMoney,
     ExcessiveInsurance,
+    BetLimit {
+        min: Rational64,
+        max: Option<Rational64>,
+    },
+    InvalidCard(String),
+    InvalidHand(String),
+    InvalidMoney(String),
+    InvalidPenetration(String),
+    UnknownCountingSystem(String),
+    ConfigSyntax {
+        line: usize,
+        reason: String,
+    },
+    ConfigValue {
+        key: String,
+        reason: String,
+    },
+    InvalidSave(String),
+    InvalidProfile(String),
 }
 
 impl Error for BlJaError {}
@@ -22,6 +43,45 @@ impl Display for BlJaError {
             BlJaError::ExcessiveInsurance => {
                 write!(f, "Excessive insurance bet.  Must be <= 1/2 of bet")
             }
+            BlJaError::BetLimit {
+                min,
+                max: Some(max),
+            } => {
+                write!(
+                    f,
+                    "Bet is outside the table limits.  Must be {} to {}",
+                    format_money(*min),
+                    format_money(*max)
+                )
+            }
+            BlJaError::BetLimit { min, max: None } => {
+                write!(
+                    f,
+                    "Bet is below the table minimum of {}",
+                    format_money(*min)
+                )
+            }
+            BlJaError::InvalidCard(card) => write!(f, "Invalid card: {}", card),
+            BlJaError::InvalidHand(hand) => write!(f, "Invalid hand: {}", hand),
+            BlJaError::InvalidMoney(money) => write!(f, "Invalid amount of money: {}", money),
+            BlJaError::InvalidPenetration(penetration) => write!(
+                f,
+                "Invalid penetration: {}.  Use a percentage such as 75% or a number of cards",
+                penetration
+            ),
+            BlJaError::UnknownCountingSystem(system) => write!(
+                f,
+                "Unknown counting system: {}.  Use Hi-Lo, KO, Omega II, Zen or Wong Halves",
+                system
+            ),
+            BlJaError::ConfigSyntax { line, reason } => {
+                write!(f, "Config error on line {}: {}", line, reason)
+            }
+            BlJaError::ConfigValue { key, reason } => {
+                write!(f, "Invalid config setting {}: {}", key, reason)
+            }
+            BlJaError::InvalidSave(reason) => write!(f, "Invalid saved game: {}", reason),
+            BlJaError::InvalidProfile(reason) => write!(f, "Invalid profile: {}", reason),
         }
     }
 }
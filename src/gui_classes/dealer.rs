@@ -1,16 +1,22 @@
 use crate::card::{Card, Visible};
-use crate::gui_classes::{GUICard, CARD_H, CARD_W};
+use crate::counting::Counter;
+use crate::gui_classes::{show_count, GUICard, CARD_H, CARD_W};
 use crate::hand::Hand;
+use crate::table::Table;
 use crate::PADDING;
 use fltk::enums::{Align, FrameType};
 use fltk::frame::Frame;
 use fltk::group::Group;
 use fltk::prelude::*;
 use fltk::widget_extends;
+use num::ToPrimitive;
 
 pub struct GUIDealer {
     group: Group,
     pub(crate) frame: Frame,
+    shoe: Frame,
+    // The count, shown when toggled on
+    count: Frame,
     num_cards: i32,
 }
 
@@ -22,6 +28,16 @@ impl GUIDealer {
             .with_label("DEALER:")
             .with_align(Align::Inside | Align::Left | Align::Center);
         group.add(&dealer);
+        let shoe = Frame::default()
+            .with_size(CARD_H * 3, CARD_H)
+            .with_align(Align::Inside | Align::Right | Align::Center);
+        group.add(&shoe);
+        let mut count = Frame::default()
+            .with_size(CARD_H * 5, CARD_H)
+            .with_align(Align::Inside | Align::Center);
+        count.set_frame(FrameType::BorderBox);
+        count.hide();
+        group.add(&count);
 
         dealer.hide();
         group.end();
@@ -29,6 +45,8 @@ impl GUIDealer {
         GUIDealer {
             group,
             frame: dealer,
+            shoe,
+            count,
             num_cards: 0,
         }
     }
@@ -52,6 +70,58 @@ impl GUIDealer {
         }
     }
 
+    /// Shows how many cards are left in the shoe and in the discard tray, with the ranks left in the shoe as
+    /// the tooltip.
+    pub fn show_shoe(&mut self, table: &Table) {
+        self.shoe.resize(
+            self.group.x() + self.group.w() - self.shoe.w() - PADDING,
+            self.group.y(),
+            self.shoe.w(),
+            self.shoe.h(),
+        );
+        self.shoe.set_label(&format!(
+            "Shoe: {}  Discards: {}",
+            table.cards_remaining(),
+            table.discards().len()
+        ));
+        let ranks: Vec<String> = table
+            .remaining_ranks()
+            .iter()
+            .map(|(rank, count)| format!("{}: {}", rank, count))
+            .collect();
+        self.shoe.set_tooltip(&ranks.join("  "));
+        self.group.redraw();
+    }
+
+    pub fn toggle_count(&mut self) {
+        if self.count.visible() {
+            self.count.hide();
+        } else {
+            self.count.show();
+        }
+        self.group.redraw();
+    }
+
+    /// Updates the count, placed to the left of the shoe, if it is shown.
+    pub fn show_count(&mut self, counter: &Counter) {
+        if !self.count.visible() {
+            return;
+        }
+        self.count.resize(
+            self.shoe.x() - self.count.w() - PADDING,
+            self.group.y(),
+            self.count.w(),
+            self.count.h(),
+        );
+        self.count.set_label(&format!(
+            "{}  Running: {}  True: {:.1}",
+            counter.system().name,
+            show_count(counter.running_count()),
+            counter.true_count().to_f64().unwrap_or(0.)
+        ));
+        self.group.redraw();
+    }
+
     pub fn remove_cards(&mut self) {
         let mut last = self.group.children() - 1;
         let first = last - self.num_cards;
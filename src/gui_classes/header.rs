@@ -10,6 +10,14 @@ use fltk::prelude::*;
 #[allow(dead_code)]
 pub struct GUIHeader {
     restart: Button,
+    count: Button,
+    review: Button,
+    #[cfg(feature = "serde")]
+    save: Button,
+    #[cfg(feature = "serde")]
+    load: Button,
+    #[cfg(feature = "serde")]
+    profile: Button,
 }
 
 impl GUIHeader {
@@ -23,6 +31,15 @@ impl GUIHeader {
             .with_label("Restart")
             .with_align(Align::Inside | Align::Center);
         button.emit(s.clone(), Message::Restart);
+        let count = header_button("Count", Message::Count, s);
+        let review = header_button("Review", Message::Review, s);
+
+        #[cfg(feature = "serde")]
+        let save = header_button("Save", Message::Save, s);
+        #[cfg(feature = "serde")]
+        let load = header_button("Load", Message::Load, s);
+        #[cfg(feature = "serde")]
+        let profile = header_button("Profile", Message::Sit, s);
 
         butg.set_type(PackType::Horizontal);
         butg.end();
@@ -40,6 +57,25 @@ impl GUIHeader {
 
         row.end();
 
-        GUIHeader { restart: button }
+        GUIHeader {
+            restart: button,
+            count,
+            review,
+            #[cfg(feature = "serde")]
+            save,
+            #[cfg(feature = "serde")]
+            load,
+            #[cfg(feature = "serde")]
+            profile,
+        }
     }
 }
+
+fn header_button(label: &str, message: Message, s: &Sender<Message>) -> Button {
+    let mut button = Button::default()
+        .with_size(BUTTON_H, BUTTON_H)
+        .with_label(label)
+        .with_align(Align::Inside | Align::Center);
+    button.emit(*s, message);
+    button
+}
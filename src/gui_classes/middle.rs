@@ -1,6 +1,6 @@
 use crate::card::{Card, Visible};
 use crate::gui_classes::{GUICard, CARD_H, CARD_W};
-use crate::hand::Action::{Double, Hit, Split, Stand};
+use crate::hand::Action::{Double, Hit, Split, Stand, Surrender};
 use crate::hand::{Action, Hand};
 use crate::Message::{Bet, Insurance};
 use crate::{Message, BORDER, PADDING, WIN_W};
@@ -10,7 +10,7 @@ use fltk::enums::FrameType;
 use fltk::enums::*;
 use fltk::frame::Frame;
 use fltk::group::Group;
-use fltk::input::{FloatInput, IntInput};
+use fltk::input::FloatInput;
 use fltk::prelude::*;
 use fltk::widget_extends;
 use std::collections::HashSet;
@@ -21,7 +21,10 @@ pub struct MiddleSection {
     pub(crate) stand: Button,
     pub(crate) double: Button,
     pub(crate) split: Button,
-    pub(crate) bet: IntInput,
+    pub(crate) surrender: Button,
+    pub(crate) even_money: Button,
+    pub(crate) hint: Button,
+    pub(crate) bet: FloatInput,
     pub(crate) insurance: FloatInput,
     pub(crate) continue_button: Button,
     num_cards: i32,
@@ -40,7 +43,7 @@ impl MiddleSection {
 
         hand.hide();
 
-        let mut bet = IntInput::default()
+        let mut bet = FloatInput::default()
             .with_size(80, 60)
             .with_pos(WIN_W / 2 - 40, group.y() + PADDING)
             .with_label("Bet:")
@@ -114,6 +117,35 @@ impl MiddleSection {
 
         double.hide();
 
+        let mut surrender = Button::default()
+            .with_align(Align::Inside | Align::Center)
+            .with_size(80, 50)
+            .right_of(&double, 2 * PADDING);
+        surrender.set_label("Surrender");
+        surrender.emit(s, Message::Play(Surrender));
+
+        surrender.hide();
+
+        let mut hint = Button::default()
+            .with_align(Align::Inside | Align::Center)
+            .with_size(80, 50)
+            .right_of(&surrender, 2 * PADDING);
+        hint.set_label("Hint");
+        hint.set_tooltip("Shows the basic strategy play");
+        hint.emit(s, Message::Hint);
+
+        hint.hide();
+
+        // Shown in place of the split button, which is not available until playing starts
+        let mut even_money = Button::default()
+            .with_align(Align::Inside | Align::Center)
+            .with_size(80, 50)
+            .left_of(&hit, 2 * PADDING);
+        even_money.set_label("Even Money");
+        even_money.emit(s, Message::EvenMoney);
+
+        even_money.hide();
+
         let mut continue_button = Button::default()
             .with_align(Align::Inside | Align::Center)
             .with_size(80, 50)
@@ -133,6 +165,9 @@ impl MiddleSection {
             stand,
             double,
             split,
+            surrender,
+            even_money,
+            hint,
             bet,
             insurance,
             num_cards: 0,
@@ -164,6 +199,8 @@ impl MiddleSection {
         self.stand.hide();
         self.split.hide();
         self.double.hide();
+        self.surrender.hide();
+        self.hint.hide();
     }
 
     pub fn show_buttons(&mut self, actions: &HashSet<Action>) {
@@ -174,8 +211,12 @@ impl MiddleSection {
                 Action::Split => self.split.show(),
                 Action::Stand => self.stand.show(),
                 Action::Double => self.double.show(),
+                Action::Surrender => self.surrender.show(),
             }
         }
+        if !actions.is_empty() {
+            self.hint.show();
+        }
     }
 
     pub fn remove_cards(&mut self) {
@@ -5,23 +5,28 @@ pub mod middle;
 pub mod player_widget;
 pub use fltk::prelude::*;
 
+use crate::counting::{parse_count, Accuracy, Counter, CountingSystem, HI_LO, QUIZ_CHANCE};
+use crate::engine::{Command, Engine, Event, Outcome, Phase, Settlement};
 pub use crate::gui_classes::card::*;
 pub use crate::gui_classes::dealer::GUIDealer;
 pub use crate::gui_classes::header::GUIHeader;
 use crate::gui_classes::middle::MiddleSection;
 use crate::gui_classes::player_widget::GUIPlayer;
-use crate::hand::{Action, HandType};
-use crate::player::{Player, Status};
-use crate::table::Table;
+use crate::hand::{Action, Hand, HandType};
+use crate::player::{format_money, parse_money};
+#[cfg(feature = "serde")]
+use crate::profile::Profiles;
+use crate::review::Review;
+use fltk::dialog;
 use fltk::enums;
 use fltk::enums::{Align, FrameType};
 use fltk::frame::Frame;
 use fltk::group::{Column, Row};
 use num::{Rational64, ToPrimitive, Zero};
-use std::cell::RefCell;
-use std::cmp::min;
-use std::ops::Deref;
-use std::rc::Rc;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha12Rng;
+#[cfg(feature = "serde")]
+use std::path::Path;
 
 pub const BUTTON_H: i32 = 80;
 pub const WIN_W: i32 = 1000;
@@ -34,20 +39,29 @@ pub const CARD_W: i32 = (CARD_H as f32 * CARD_RATIO) as i32;
 const CARD_RATIO: f32 = 2.5 / 3.5;
 pub const EIGHTH: i32 = WIN_H / 8;
 
-/// This is the main struct for GUI manipulation and overall flow of the game.  The sequence of playing,
-/// from dealing, to betting, to insurance, to hitting or standing, and finally payout is done here.  Most of the
-/// logic for rules occurs in the non-gui structs.
+/// This is the main struct for GUI manipulation.  The sequence of playing, from dealing, to betting, to
+/// insurance, to hitting or standing, and finally payout is run by the `Engine`.  This struct sends the
+/// player's input to the engine as commands, and renders the events that come back.
 pub struct GUIMain {
     pub(crate) header: GUIHeader,
     pub(crate) dealer: GUIDealer,
     pub(crate) message: Frame,
     pub(crate) middle: MiddleSection,
     pub(crate) players_gui: Vec<GUIPlayer>,
-    pub(crate) table: Table,
-    pub(crate) index: usize,
-    pub(crate) active_players: Vec<Rc<RefCell<Player>>>,
-    pub(crate) hand_num: usize,
-    pub(crate) cont_func: fn(&mut GUIMain),
+    pub(crate) engine: Engine,
+    // The hand shown in the middle section, if any
+    pub(crate) shown: Option<(usize, usize)>,
+    // Counts the cards as they are dealt, for the count shown next to the dealer and the trainer
+    pub(crate) counter: Counter,
+    // How well the count has been kept, when training
+    pub(crate) trainer: Option<Accuracy>,
+    // Decides when the trainer asks, so a seeded game asks at the same points
+    pub(crate) quiz_rng: ChaCha12Rng,
+    // Each player's plays checked against basic strategy, for the end of session report
+    pub(crate) review: Review,
+    // Profiles of the seated players, if they can be saved
+    #[cfg(feature = "serde")]
+    pub(crate) profiles: Option<Profiles>,
 }
 
 impl GUIMain {
@@ -57,22 +71,40 @@ impl GUIMain {
         message: Frame,
         middle: MiddleSection,
         players_gui: Vec<GUIPlayer>,
-        table: Table,
+        engine: Engine,
     ) -> GUIMain {
+        let counter = Counter::new(HI_LO, engine.table());
         GUIMain {
             header,
             dealer,
             message,
             middle,
             players_gui,
-            table,
-            index: 0,
-            active_players: vec![],
-            hand_num: 0,
-            cont_func: GUIMain::setup_game,
+            engine,
+            shown: None,
+            counter,
+            trainer: None,
+            quiz_rng: ChaCha12Rng::from_entropy(),
+            review: Review::default(),
+            #[cfg(feature = "serde")]
+            profiles: None,
         }
     }
 
+    /// Counts the table's shoe with the system from here on.  With the accuracy kept so far, the player is
+    /// asked for the count now and then.
+    pub fn count_with(&mut self, system: CountingSystem, trainer: Option<Accuracy>) {
+        self.counter = Counter::new(system, self.engine.table());
+        self.trainer = trainer;
+        self.dealer.show_count(&self.counter);
+    }
+
+    /// Shows or hides the count next to the dealer.
+    pub fn toggle_count(&mut self) {
+        self.dealer.toggle_count();
+        self.dealer.show_count(&self.counter);
+    }
+
     pub fn setup_game(&mut self) {
         // Clear the gui if needed (has been restarted)
         self.dealer.remove_cards();
@@ -82,7 +114,12 @@ impl GUIMain {
         self.middle.insurance.hide();
 
         // assign players to gui
-        for (p, gui) in self.table.player_iter().zip(self.players_gui.iter_mut()) {
+        for (p, gui) in self
+            .engine
+            .table()
+            .player_iter()
+            .zip(self.players_gui.iter_mut())
+        {
             gui.title.set_label(&p.borrow().name());
             gui.points.set_label(&p.borrow().display_money());
             gui.bet.set_label("0");
@@ -90,663 +127,604 @@ impl GUIMain {
             gui.player = Some(p);
             gui.redraw();
         }
+        self.dealer.show_shoe(self.engine.table());
     }
 
-    /// Starts a full round of players.  Active players will but updated so that only players with money at this
-    /// point in time can play.
+    /// Starts the first round of play.
     pub fn start_round(&mut self) {
-        if let Some(_) = self.set_active_players_get_first() {
-            if self.table.reshuffle {
-                // Do not display shuffling message for single decks
-                if self.table.decks() > 1 {
-                    self.message.set_label("Shuffling the deck.");
-                    self.middle.continue_button.show();
-                    self.table.shuffle();
-                    self.cont_func = Self::start_betting;
-                    return;
-                }
+        let events = self.engine.start();
+        self.render(events);
+    }
 
-                self.table.shuffle()
+    /// Saves the game to the file, showing whether it worked as the message.
+    #[cfg(feature = "serde")]
+    pub fn save_game(&mut self, path: &Path) {
+        let message = match crate::save::save(&self.engine, path) {
+            Ok(()) => format!("Game saved to {}", path.display()),
+            Err(e) => format!("Could not save: {}", e),
+        };
+        self.message.set_label(&message);
+    }
+
+    /// Redraws the table for a game loaded part way through a round.
+    pub fn resume(&mut self) {
+        self.shown = None;
+        self.setup_game();
+
+        for player in 0..self.players_gui.len() {
+            let (bet, insurance) = {
+                let theplayer = self.engine.table().player(player).unwrap().borrow();
+                let bet: Rational64 = theplayer.hand_iter().filter_map(Hand::bet).sum();
+                (bet, theplayer.insurance())
+            };
+            self.players_gui[player].set_bet(&format_money(bet));
+            self.players_gui[player]
+                .set_insurance(&format!("{:.2}", insurance.to_f64().unwrap_or(0.)));
+        }
+
+        let dealer_hand = self.engine.table().dealer.get_hand(0).unwrap().clone();
+        if dealer_hand.num_cards() > 0 {
+            self.dealer.frame.show();
+            for card in dealer_hand.card_iter() {
+                self.dealer.add_card(card);
             }
-            self.start_betting()
-        } else {
-            self.game_over()
         }
-    }
 
-    fn start_betting(&mut self) {
-        // Set up for betting
-        let string = self
-            .table
-            .player(self.index)
-            .unwrap()
-            .borrow()
-            .name()
-            .to_string()
-            + ": Place your bet. ";
-        self.set_current();
-        self.message.set_label(&string);
+        let message = match self.engine.phase() {
+            Phase::Betting { player }
+            | Phase::Surrender { player }
+            | Phase::Insurance { player }
+            | Phase::EvenMoney { player } => {
+                self.set_current(player);
+                format!("Game loaded.  {}'s turn", self.player_name(player))
+            }
+            Phase::Playing { player, hand }
+            | Phase::HandOver { player, hand }
+            | Phase::Settled { player, hand } => {
+                self.set_current(player);
+                self.show_hand(player, hand);
+                format!("Game loaded.  {}'s turn", self.player_name(player))
+            }
+            Phase::GameOver => "Game over!  All players are out of money.".to_string(),
+            Phase::Shuffled | Phase::DealerDone => "Game loaded.".to_string(),
+        };
+        self.message.set_label(&message);
+        self.update_controls();
         self.message.redraw();
-        self.middle.bet.show();
     }
 
-    pub fn set_bet(&mut self, str: String) {
-        if let Ok(bet) = str.parse() {
-            let rc = Rc::clone(self.table.player(self.index).unwrap());
-
-            // Update the player info for the bet. Returns Error if not enough money
-            let result = rc
-                .deref()
-                .borrow_mut()
-                .place_bet(Rational64::from_integer(bet));
-
-            match result {
-                Err(e) => {
-                    self.message
-                        .set_label(&format!("{}: {}", self.player_name(), e.to_string()));
-                    return;
-                }
-                _ => (),
+    /// Asks the player whose turn it is to bet which profile to sit down with.  Their bankroll replaces the
+    /// money at the seat.
+    #[cfg(feature = "serde")]
+    pub fn pick_profile(&mut self) {
+        let seat = match self.engine.phase() {
+            Phase::Betting { player } => player,
+            _ => {
+                self.message
+                    .set_label("Profiles are picked on your turn to bet");
+                return;
             }
-
-            // Update GUI
-            self.middle.bet.set_value("0");
-            self.players_gui[self.index].bet.set_label(&bet.to_string());
-            self.players_gui[self.index]
-                .points
-                .set_label(&rc.borrow().display_money());
-
-            match self.next_player() {
-                Some(_) => self.start_betting(),
-                None => self.setup_playing(),
+        };
+        let profiles = match &mut self.profiles {
+            Some(profiles) => profiles,
+            None => {
+                self.message.set_label("Profiles can not be saved");
+                return;
             }
-        }
-        // else return, and do not set the bet
-    }
-
-    /// Set up conditions for playing this round
-    fn setup_playing(&mut self) {
-        self.table.deal_players();
-        self.middle.bet.hide();
-        self.dealer.frame.show();
-
-        self.first_player();
+        };
 
-        for dcard in self.table.dealer.hand_iter().next().unwrap().card_iter() {
-            self.dealer.add_card(dcard);
+        let saved = profiles.store().names().unwrap_or_default().join(", ");
+        let prompt = if saved.is_empty() {
+            format!("Profile name for seat {}", seat + 1)
+        } else {
+            format!("Profile name for seat {}.  Saved: {}", seat + 1, saved)
+        };
+        let name = match dialog::input_default(&prompt, "") {
+            Some(name) => name,
+            None => return,
+        };
+        if let Err(e) = profiles.sit(seat, &name, &mut self.engine) {
+            self.message.set_label(&format!("{}", e));
+            return;
         }
 
-        for player in self.players_gui.iter_mut() {
-            player.redraw();
+        let stats = profiles.get(seat).unwrap().stats().clone();
+        let name = self.player_name(seat);
+        self.message.set_label(&format!(
+            "{} sits down.  {} hands played, net winnings {}. Place your bet. ",
+            name, stats.hands, stats.net
+        ));
+        self.players_gui[seat].title.set_label(&name);
+        self.players_gui[seat].redraw();
+        self.update_points(seat);
+        self.fill_default_bet(seat);
+    }
+
+    /// Puts the default bet from the player's profile in the bet input.
+    #[cfg(feature = "serde")]
+    fn fill_default_bet(&mut self, player: usize) {
+        let bet = self
+            .profiles
+            .as_ref()
+            .and_then(|profiles| profiles.get(player))
+            .and_then(|profile| profile.preferences().default_bet);
+        if let Some(bet) = bet {
+            self.middle.bet.set_value(&format_money(bet));
         }
-        self.check_dealer();
     }
 
-    fn check_dealer(&mut self) {
-        let dealer_hand = self.table.dealer.get_hand(0).unwrap().clone();
-        // Offer insurance if card is an ace
-        if dealer_hand.is_first_card_ace() {
-            self.middle.hide_buttons();
-            self.offer_insurance()
-        } else {
-            self.peek_for_natural()
+    /// Writes the seated profiles' bankrolls and stats at the end of the round.
+    #[cfg(feature = "serde")]
+    fn save_profiles(&mut self) {
+        if let Some(profiles) = &mut self.profiles {
+            if let Err(e) = profiles.save(self.engine.table()) {
+                self.message
+                    .set_label(&format!("Could not save profiles: {}", e));
+            }
         }
     }
 
-    /// Used to peek under the dealers card to see if it has a blackjack
-    fn peek_for_natural(&mut self) {
-        let dealer_hand = self.table.dealer.get_hand(0).unwrap().clone();
-        if dealer_hand.peek_for_natural() {
-            // Round is over if the dealer has a natural
-            self.table.dealer.get_hand_mut(0).unwrap().flip_over();
-            self.dealer.flip_over(&dealer_hand);
-            self.settle_setup();
-        } else {
-            self.start_playing();
+    pub fn set_bet(&mut self, str: String) {
+        if let Ok(bet) = parse_money(&str) {
+            self.send(Command::Bet(bet));
         }
-    }
-
-    /// Sets up conditions for insurance
-    fn offer_insurance(&mut self) {
-        self.set_current();
-        let player = self.active_players[self.index].borrow();
-        self.message
-            .set_label(&format!("{}, place insurance bet", player.name()));
-        self.middle.insurance.show();
+        // else return, and do not set the bet
     }
 
     /// Sets insurance for the player
     pub fn set_insurance(&mut self, bet_str: String) {
         if let Ok(bet) = bet_str.parse::<f64>() {
-            let rc = Rc::clone(&self.active_players[self.index]);
-
             // Truncate the float at 2 decimal points and convert to rational
             let bet = (bet * 100.0) as i64;
-            let bet = Rational64::from((bet, 100));
-
-            // Update the player info for the bet. Returns Error if not enough money
-            let result = rc.deref().borrow_mut().set_insurance(bet);
-
-            match result {
-                Err(e) => {
-                    self.message
-                        .set_label(&format!("{}: {}", self.player_name(), e.to_string()));
-                    return;
-                }
-                _ => (),
-            }
-
-            // Update GUI
-            self.middle.insurance.set_value("0");
-            self.players_gui[self.index]
-                .insurance
-                .set_label(&format!("{:.2}", &bet.to_f64().unwrap_or(0.)));
-            self.players_gui[self.index]
-                .points
-                .set_label(&rc.borrow().display_money());
-
-            match self.next_player() {
-                Some(_) => self.offer_insurance(),
-                None => {
-                    self.middle.insurance.hide();
-                    self.first_player();
-                    self.peek_for_natural()
-                }
-            }
+            self.send(Command::Insure(Rational64::from((bet, 100))));
         }
     }
-    /// This is where each player begins play
-    fn start_playing(&mut self) {
-        self.set_current();
-        self.middle.remove_cards();
-        let player = Rc::clone(self.active_players.get(self.index).unwrap());
-        let player = player.borrow();
-        let str: String = format!("{}'s turn to play", player.name());
-        self.message.set_label(&str);
 
-        if let Some(hand) = player.get_hand(self.hand_num) {
-            for card in hand.card_iter() {
-                self.middle.add_card(card);
-            }
-
-            // Check for natural
-            match hand.hand_type() {
-                HandType::Natural => {
-                    self.message.set_label("Blackjack!");
-                    self.middle.continue_button.show();
-                    return self.inc_turn();
-                }
-                _ => (),
-            }
-            let actions = player.actions(self.hand_num);
-            self.middle.show_buttons(&actions);
-
-            // This section can be reached after splitting aces
-            if actions.len() == 0 {
-                self.middle.continue_button.show();
-                self.message.set_label(&format!(
-                    "{} no further actions available for this hand",
-                    player.name()
-                ));
-                return self.inc_turn();
-            }
-        }
-    }
-
-    /// Increment's the struct's counters for either hand index or current player index. Priority placed
-    /// on the current players hands (in case of split) over the next player. Only for use in during the playing phase
-    pub fn inc_turn(&mut self) {
-        // Increment next available hand to the currently player first
-        if self.active_players[self.index].borrow().num_hands() > self.hand_num + 1 {
-            self.hand_num += 1;
-            self.cont_func = Self::start_playing;
-            return;
-        }
-        // If no extra hand avaiable, increment the next player
-        self.hand_num = 0;
-        if let Some(_) = self.next_player() {
-            self.cont_func = Self::start_playing;
-        } else {
-            self.cont_func = Self::dealer_play;
-        }
-    }
-
-    /// Used as a pause button.
-    pub fn continue_play(&mut self) {
-        self.middle.continue_button.hide();
-        (self.cont_func)(self)
+    pub fn take_even_money(&mut self) {
+        self.send(Command::EvenMoney);
     }
 
     pub fn perform_action(&mut self, action: Action) {
-        match action {
-            Action::Hit => self.hit(),
-            Action::Stand => self.stand(),
-            Action::Split => self.split(),
-            Action::Double => self.double(),
+        let decision = match (self.engine.decision(), self.engine.phase()) {
+            (Some((hand, up_card)), Phase::Playing { player, .. }) => Some((
+                self.player_name(player),
+                hand,
+                up_card,
+                self.engine.actions(),
+            )),
+            _ => None,
+        };
+        // Only plays the engine accepts are reviewed
+        if self.send(Command::Act(action)) {
+            if let Some((name, hand, up_card, actions)) = decision {
+                self.review.player(&name).check(
+                    &hand,
+                    &up_card,
+                    self.engine.table().rules(),
+                    &actions,
+                    action,
+                );
+            }
         }
     }
 
-    fn hit(&mut self) {
+    /// Shows the basic strategy play for the hand being played.
+    pub fn hint(&mut self) {
+        if let (Some(action), Phase::Playing { player, .. }) =
+            (self.engine.advice(), self.engine.phase())
         {
-            let player = Rc::clone(&self.active_players[self.index]);
-            let mut hand = player.borrow_mut();
-            let hand = hand.get_hand_mut(self.hand_num).unwrap();
-            // deal a card
-            let card = self.table.deal_card(true);
-            // update gui
-            self.middle.add_card(&card);
-            // add to players hand
-            hand.insert(card);
-        }
-
-        let player = Rc::clone(&self.active_players[self.index]);
-        let actions = player.borrow().actions(self.hand_num);
-        self.middle.show_buttons(&actions);
-        if actions.is_empty() {
-            self.middle.continue_button.show();
-            // Play the split hand, if avail
-            if player.borrow().num_hands() > self.hand_num + 1 {
-                self.hand_num += 1;
-                self.cont_func = Self::start_playing;
-                self.message.set_label("Playing split hand");
-                return;
-            }
-
-            if player.borrow().get_hand(self.hand_num).unwrap().hand_type() == HandType::Bust {
-                self.message.set_label("Bust");
-            } else {
-                self.message.set_label(&format!(
-                    "All available actions are complete for {}",
-                    player.borrow().name()
-                ));
-            }
-            // Move on to the next player
-            self.inc_turn();
+            self.message.set_label(&format!(
+                "{}: basic strategy says {:?}",
+                self.player_name(player),
+                action
+            ));
+            self.message.redraw();
         }
     }
 
-    fn stand(&mut self) {
-        let player = Rc::clone(&self.active_players[self.index]);
-        self.message
-            .set_label(&format!("{} stands.", player.borrow().name()));
-        self.middle.continue_button.show();
-        self.middle.hide_buttons();
-
-        self.inc_turn();
+    /// Shows each player's most frequent and most costly mistakes so far.
+    pub fn show_review(&self) {
+        dialog::message_default(&self.review.report());
     }
 
-    fn split(&mut self) {
-        let player = Rc::clone(&self.active_players[self.index]);
-        self.message
-            .set_label(&format!("{} splits", player.borrow().name()));
-        self.middle.hide_buttons();
-        self.middle.continue_button.show();
-
-        let card1 = self.table.deal_card(true);
-        let card2 = self.table.deal_card(true);
-        player.borrow_mut().split_hand(self.hand_num, card1, card2);
-
-        self.players_gui[self.index]
-            .points
-            .set_label(&player.borrow().display_money());
-
-        self.cont_func = Self::start_playing;
+    /// Used as a pause button.
+    pub fn continue_play(&mut self) {
+        self.send(Command::Continue);
     }
 
-    fn double(&mut self) {
-        {
-            let player = Rc::clone(&self.active_players[self.index]);
-            let bet = player
-                .borrow()
-                .get_hand(self.hand_num)
-                .unwrap()
-                .bet()
-                .unwrap_or(Rational64::zero());
-
-            let mut player = player.borrow_mut();
-            let avail_money = player.money();
-
-            let hand = player.get_hand_mut(self.hand_num).unwrap();
-
-            let double_bet = min(avail_money, bet);
-            if double_bet < bet {
-                self.middle.continue_button.show();
-                self.middle.hide_buttons();
-                self.message.set_label("Doubling for less");
-            }
-
-            let hand = hand.double().unwrap();
-            let card = self.table.deal_card(true);
-            let hand = hand.insert(card);
-
-            player.replace_hand(self.hand_num, hand);
-
-            player.double(double_bet);
-            self.middle.add_card(&card);
-            self.players_gui[self.index].set_bet(
-                &player
-                    .get_hand(self.hand_num)
-                    .unwrap()
-                    .bet()
-                    .unwrap_or(Rational64::zero())
-                    .to_string(),
-            );
-            self.players_gui[self.index].set_points(&player.display_money());
-        }
-
-        if self.is_bust() {
-            self.message.set_label("BUST");
-        } else {
-            self.message
-                .set_label(&format!("No more actions for {}.", self.player_name()));
+    /// Sends the command to the engine, returning whether it was accepted.  Errors, such as not having enough
+    /// money, are shown as the message.
+    fn send(&mut self, command: Command) -> bool {
+        match self.engine.handle(command) {
+            Ok(events) => {
+                self.render(events);
+                true
+            }
+            Err(e) => {
+                let name = match self.engine.phase() {
+                    Phase::Betting { player }
+                    | Phase::Insurance { player }
+                    | Phase::Surrender { player }
+                    | Phase::EvenMoney { player }
+                    | Phase::Playing { player, .. } => self.player_name(player),
+                    _ => "".to_string(),
+                };
+                self.message.set_label(&format!("{}: {}", name, e));
+                false
+            }
         }
-
-        self.middle.continue_button.show();
-        self.middle.hide_buttons();
-
-        self.inc_turn();
     }
 
-    fn dealer_play(&mut self) {
-        self.message.set_label("Dealer's turn");
-        self.players_gui[self.index].deactivate_player();
-        self.middle.remove_cards();
-
-        let hand = self.table.dealer_mut().get_hand_mut(0).unwrap();
-        hand.flip_over();
-        self.dealer.flip_over(&hand);
-
-        while self.table.dealer.score(0) < 17 {
-            let card = self.table.deal_card(true);
-            self.dealer.add_card(&card);
-            self.table.dealer.get_hand_mut(0).unwrap().insert(card);
+    fn render(&mut self, events: Vec<Event>) {
+        for event in events.iter() {
+            self.counter.see(event);
+            self.render_event(*event);
         }
+        self.dealer.show_count(&self.counter);
+        self.update_controls();
+        self.message.redraw();
 
-        self.middle.continue_button.show();
-        self.cont_func = Self::settle_setup;
-    }
-
-    fn settle_setup(&mut self) {
-        self.first_player();
-        self.hand_num = 0;
-
-        self.settle()
-    }
-
-    fn settle(&mut self) {
-        {
-            self.middle.continue_button.show();
-
-            let dealer_hand = self.table.dealer.get_hand(0).unwrap();
-            // dealer_hand.flip_over();
-            //
-            // self.dealer.flip_over(dealer_hand);
-
-            let dealer_handtype = dealer_hand.hand_type();
-            match dealer_handtype {
-                HandType::Natural => self.settle_with_dealer_natrual(),
-                HandType::Bust => self.settle_with_dealer_bust(),
-                _ => self.settle_with_dealer_other(),
-            }
+        // The trainer asks now and then as a round's betting begins
+        let round_begins = events.iter().any(|e| matches!(e, Event::BetTurn(_)))
+            && !events.iter().any(|e| matches!(e, Event::BetPlaced { .. }));
+        if self.trainer.is_some() && round_begins && self.quiz_rng.gen_bool(QUIZ_CHANCE) {
+            self.quiz();
         }
-        self.cont_func = Self::clean_after_settle;
     }
 
-    /// used when the dealer has a natural blackjack.  Everyone loses except for anyone with a natural blackjack.  Insurance is paid out.
-    fn settle_with_dealer_natrual(&mut self) {
-        let playerrc = Rc::clone(&self.active_players[self.index]);
-        let hand = playerrc.borrow().get_hand(self.hand_num).unwrap().clone();
-        let mut player = playerrc.borrow_mut();
-        self.middle.add_cards(&hand);
-
-        let ins_payout = player.insurance() * Rational64::from_integer(2);
-
-        match hand.hand_type() {
-            HandType::Natural => {
-                let string = match ins_payout > Rational64::zero() {
-                    true => format!(
-                        "Tie. Dealer and {} have Blackjacks. Insurance pays {:.2}",
-                        player.name(),
-                        ins_payout
+    /// Asks for the running and true count, then shows whether they were right and the accuracy so far.
+    fn quiz(&mut self) {
+        let running = match ask_count("Count check!  What is the running count?") {
+            Some(count) => count,
+            None => return,
+        };
+        let true_count = match ask_count("And the true count?") {
+            Some(count) => count,
+            None => return,
+        };
+        let accuracy = match &mut self.trainer {
+            Some(accuracy) => accuracy,
+            None => return,
+        };
+        let (running_right, true_right) = accuracy.check(&self.counter, running, true_count);
+        let verdict = |right: bool| if right { "right" } else { "wrong" };
+        dialog::message_default(&format!(
+            "The running count is {}, so {} is {}.\nThe true count is {:.1}, so {} is {}.\n\n\
+             Running counts right: {} of {}\nTrue counts right: {} of {}",
+            show_count(self.counter.running_count()),
+            show_count(running),
+            verdict(running_right),
+            self.counter.true_count().to_f64().unwrap_or(0.),
+            show_count(true_count),
+            verdict(true_right),
+            accuracy.running_right,
+            accuracy.asked,
+            accuracy.true_right,
+            accuracy.asked
+        ));
+    }
+
+    fn render_event(&mut self, event: Event) {
+        match event {
+            Event::Shuffled => {
+                let table = self.engine.table();
+                let mut message = match (table.penetrations().last(), table.average_penetration()) {
+                    (Some(reached), Some(average)) => format!(
+                        "Shuffling the deck.  {}% of the last shoe was dealt, {}% on average.",
+                        (reached * 100).round(),
+                        (average * 100).round()
                     ),
-                    false => format!("Tie. Dealer and {} have Blackjacks", player.name()),
+                    _ => "Shuffling the deck.".to_string(),
                 };
-
-                self.message.set_label(&string);
-                player.collect(hand.bet().unwrap() + ins_payout);
+                self.dealer.show_shoe(table);
+                // Burned cards are the only discards right after a shuffle
+                match table.discards() {
+                    [] => (),
+                    burned if table.rules().burn_face_up => {
+                        let burned: Vec<String> = burned.iter().map(|c| c.to_string()).collect();
+                        message.push_str(&format!("  Burned: {}", burned.join(" ")));
+                    }
+                    burned => message.push_str(&format!("  {} burned.", burned.len())),
+                }
+                self.message.set_label(&message);
             }
-
-            _ => {
-                let string = match ins_payout > Rational64::zero() {
-                    true => format!(
-                        "Dealer has a blackjack. Insurance pays {} {:.2}",
-                        player.name(),
-                        ins_payout
-                    ),
-                    false => format!("Dealer has a blackjack, {} lost", player.name()),
-                };
-                self.message.set_label(&string);
-                player.collect(ins_payout);
+            Event::DiscardsShuffled(_) => self
+                .message
+                .set_label("Out of cards.  Shuffling the discards to finish the round."),
+            Event::BetTurn(player) => {
+                self.set_current(player);
+                self.message
+                    .set_label(&format!("{}: Place your bet. ", self.player_name(player)));
+                #[cfg(feature = "serde")]
+                self.fill_default_bet(player);
             }
-        }
-    }
-
-    /// Used if the dealer busted during play.  Everyone wins unless they busted. Natural blackjacks are paid out 1.5 times.
-    fn settle_with_dealer_bust(&mut self) {
-        let playerrc = Rc::clone(&self.active_players[self.index]);
-        let hand = playerrc.borrow().get_hand(self.hand_num).unwrap().clone();
-        let mut player = playerrc.borrow_mut();
-        self.middle.add_cards(&hand);
-
-        let handnum = if self.hand_num > 0 {
-            format!(", Hand: {}", self.hand_num + 1)
-        } else {
-            "".to_string()
-        };
-        match hand.hand_type() {
-            HandType::Bust => {
-                // Player still loses due to busting first
+            Event::BetPlaced { player, bet } => {
+                self.middle.bet.set_value("0");
+                self.players_gui[player].set_bet(&format_money(bet));
+                self.update_points(player);
+            }
+            Event::PlayerCard { player, hand, card } => {
+                if self.shown == Some((player, hand)) {
+                    self.middle.add_card(&card);
+                }
+            }
+            Event::DealerCard(card) => {
+                self.dealer.frame.show();
+                self.dealer.add_card(&card);
+            }
+            Event::SurrenderTurn(player) => {
+                self.set_current(player);
+                self.message.set_label(&format!(
+                    "{}, surrender before the dealer checks for blackjack?",
+                    self.player_name(player)
+                ));
+            }
+            Event::InsuranceTurn(player) => {
+                self.set_current(player);
+                self.message.set_label(&format!(
+                    "{}, place insurance bet",
+                    self.player_name(player)
+                ));
+            }
+            Event::InsurancePlaced { player, bet } => {
+                self.middle.insurance.set_value("0");
+                self.players_gui[player]
+                    .set_insurance(&format!("{:.2}", &bet.to_f64().unwrap_or(0.)));
+                self.update_points(player);
+            }
+            Event::EvenMoneyTurn(player) => {
+                self.set_current(player);
+                self.message.set_label(&format!(
+                    "{} has blackjack. Take even money?",
+                    self.player_name(player)
+                ));
+            }
+            Event::EvenMoneyPaid { player, .. } => {
                 self.message
-                    .set_label(&format!("{}{} Busts", player.name(), handnum));
+                    .set_label(&format!("{} takes even money", self.player_name(player)));
+                self.update_points(player);
             }
-
-            HandType::Natural => {
+            Event::HoleCardRevealed(_) => {
+                let hand = self.engine.table().dealer.get_hand(0).unwrap().clone();
+                self.dealer.flip_over(&hand);
+            }
+            // Burned cards are shown with the shuffle
+            Event::Burned(_) | Event::DealerNatural => (),
+            Event::PlayTurn { player, hand } => {
+                self.set_current(player);
+                self.show_hand(player, hand);
                 self.message
-                    .set_label(&format!("{}{} Blackjack!", player.name(), handnum));
-                player.collect(hand.bet().unwrap() * Rational64::from((5, 2)));
+                    .set_label(&format!("{}'s turn to play", self.player_name(player)));
             }
-
-            _ => {
+            Event::Split { player, .. } => {
+                self.message
+                    .set_label(&format!("{} splits", self.player_name(player)));
+                self.update_points(player);
+            }
+            Event::Doubled { player, bet, .. } => {
                 self.message.set_label(&format!(
-                    "{}{} won! Player score: {}, Dealer Bust",
-                    player.name(),
-                    handnum,
-                    hand.score(),
+                    "No more actions for {}.",
+                    self.player_name(player)
                 ));
-                player.collect(hand.bet().unwrap() * 2);
+                self.players_gui[player].set_bet(&format_money(bet));
+                self.update_points(player);
             }
-        }
-    }
-
-    /// Used for all other situations where the dealer has not had a natural blackjack or has busted.
-    fn settle_with_dealer_other(&mut self) {
-        let playerrc = Rc::clone(&self.active_players[self.index]);
-        let hand = playerrc.borrow().get_hand(self.hand_num).unwrap().clone();
-        let mut player = playerrc.borrow_mut();
-        self.middle.add_cards(&hand);
-
-        let dealer_score = self.table.dealer.score(0);
-        let player_score = player.score(self.hand_num);
-
-        let handnum = if self.hand_num > 0 {
-            format!(", Hand: {}", self.hand_num + 1)
-        } else {
-            "".to_string()
-        };
-
-        match hand.hand_type() {
-            HandType::Bust => {
-                // Player still loses due to busting first
+            Event::Stood { player, .. } => {
                 self.message
-                    .set_label(&format!("{}{} Busts", player.name(), handnum));
+                    .set_label(&format!("{} stands.", self.player_name(player)));
             }
-
-            HandType::Natural => {
+            Event::Surrendered { player, .. } => {
                 self.message
-                    .set_label(&format!("{}{} Blackjack!", player.name(), handnum));
-                player.collect(hand.bet().unwrap() * Rational64::from((5, 2)));
+                    .set_label(&format!("{} surrenders.", self.player_name(player)));
+                self.update_points(player);
             }
-
-            _ => {
-                // If player won
-                if player_score > dealer_score {
-                    self.message.set_label(&format!(
-                        "{}{} won! Player score: {}, Dealer score {}",
-                        player.name(),
-                        handnum,
-                        player_score,
-                        dealer_score
-                    ));
-                    player.collect(hand.bet().unwrap() * 2);
-                }
-                // If player tied with dealer
-                else if player_score == dealer_score {
-                    self.message.set_label(&format!(
-                        "{}{} Tie! Player score: {}, Dealer score {}",
-                        player.name(),
-                        handnum,
-                        player_score,
-                        dealer_score
-                    ));
-                    player.collect(hand.bet().unwrap());
+            Event::HandOver {
+                player, hand_type, ..
+            } => {
+                let message = match hand_type {
+                    HandType::Natural => "Blackjack!".to_string(),
+                    HandType::Bust => "Bust".to_string(),
+                    HandType::Surrendered => format!("{} surrendered", self.player_name(player)),
+                    HandType::EvenMoney => {
+                        format!("{} was paid even money", self.player_name(player))
+                    }
+                    HandType::Doubled | HandType::DoubledSoft => {
+                        format!("No more actions for {}.", self.player_name(player))
+                    }
+                    _ => format!(
+                        "{} no further actions available for this hand",
+                        self.player_name(player)
+                    ),
+                };
+                self.message.set_label(&message);
+            }
+            Event::DealerTurn => {
+                self.message.set_label("Dealer's turn");
+                for player in self.players_gui.iter_mut() {
+                    player.deactivate_player();
                 }
-                // If player lost
-                else {
-                    self.message.set_label(&format!(
-                        "Sorry, {}{} lost. Player score: {}, Dealer score {}",
-                        player.name(),
-                        handnum,
-                        player_score,
-                        dealer_score
-                    ));
+                self.middle.remove_cards();
+                self.shown = None;
+            }
+            Event::Settled(settlement) => {
+                #[cfg(feature = "serde")]
+                if let Some(profiles) = &mut self.profiles {
+                    profiles.record(&settlement);
                 }
+                self.render_settlement(&settlement)
             }
-        }
-    }
-
-    /// Clean GUI after player settles upo.
-    fn clean_after_settle(&mut self) {
-        self.players_gui[self.index].set_bet("0");
-        self.players_gui[self.index].set_insurance("0");
-
-        {
-            let mut player = self.active_players[self.index].borrow_mut();
-            player.reset_after_round(self.hand_num);
-            self.players_gui[self.index].set_points(&player.display_money());
-        }
-
-        if let Some(_) = self.next_hand() {
-            self.settle();
-        } else {
-            self.hand_num = 0;
-            if let Some(_) = self.next_player() {
-                self.settle()
-            } else {
-                // Start next round
+            Event::RoundOver => {
                 self.dealer.remove_cards();
+                self.dealer.show_shoe(self.engine.table());
                 self.middle.remove_cards();
-                self.table.dealer.reset_after_round(0);
-
-                self.start_round();
+                self.shown = None;
+                #[cfg(feature = "serde")]
+                self.save_profiles();
+            }
+            Event::GameOver => {
+                self.message
+                    .set_label("Game over!  All players are out of money.");
+                self.show_review();
             }
         }
     }
 
-    pub fn set_current(&mut self) {
-        for player in self.players_gui.iter_mut() {
-            player.deactivate_player();
-        }
-        let player = &mut self.players_gui[self.index];
-        player.activate_player();
-    }
+    /// Shows the controls for the command the engine is waiting on.
+    fn update_controls(&mut self) {
+        self.middle.hide_buttons();
+        self.middle.bet.hide();
+        self.middle.insurance.hide();
+        self.middle.even_money.hide();
+        self.middle.continue_button.hide();
 
-    /// Sets the active players and returns the first person.  Used at beginning of round.
-    fn set_active_players_get_first(&mut self) -> Option<()> {
-        self.active_players = self.table.player_iter().collect();
-        self.index = 0;
-        if let Some(player) = self.active_players.get(0) {
-            if player.borrow().status() == Status::Playing {
-                return Some(());
+        match self.engine.phase() {
+            Phase::Betting { .. } => {
+                let rules = self.engine.table().rules();
+                let limits = match rules.max_bet {
+                    Some(max) => format!(
+                        "Table limits: {} to {}",
+                        format_money(rules.min_bet),
+                        format_money(max)
+                    ),
+                    None => format!("Table minimum: {}", format_money(rules.min_bet)),
+                };
+                self.middle.bet.set_tooltip(&limits);
+                self.middle.bet.show();
+            }
+            Phase::Insurance { .. } => self.middle.insurance.show(),
+            Phase::Surrender { .. } => {
+                self.middle.surrender.show();
+                self.middle.continue_button.show();
             }
+            Phase::EvenMoney { .. } => {
+                self.middle.even_money.show();
+                self.middle.continue_button.show();
+            }
+            Phase::Playing { .. } => {
+                let actions = self.engine.actions();
+                self.middle.show_buttons(&actions);
+            }
+            Phase::GameOver => (),
+            _ => self.middle.continue_button.show(),
         }
-        self.next_player()
     }
 
-    /// Gets the first player. Used during play / insurance / settlement as any player can be actively playing, but with no extra money.
-    fn first_player(&mut self) -> Option<()> {
-        self.index = 0;
-        if let Some(player) = self.active_players.get(0) {
-            if player.borrow().status() == Status::Playing {
-                return Some(());
+    fn show_hand(&mut self, player: usize, hand: usize) {
+        let thehand = self.hand(player, hand);
+        self.middle.add_cards(&thehand);
+        self.shown = Some((player, hand));
+    }
+
+    fn render_settlement(&mut self, settlement: &Settlement) {
+        let Settlement {
+            player,
+            hand,
+            outcome,
+            insurance,
+            ..
+        } = *settlement;
+        self.show_hand(player, hand);
+
+        let name = self.player_name(player);
+        let dealer_hand = self.engine.table().dealer.get_hand(0).unwrap().clone();
+        let dealer_score = dealer_hand.score();
+        let player_score = self.hand(player, hand).score();
+        let handnum = if hand > 0 {
+            format!(", Hand: {}", hand + 1)
+        } else {
+            "".to_string()
+        };
+
+        let message = match (dealer_hand.hand_type(), outcome) {
+            (_, Outcome::Surrender) => format!("{}{} surrendered", name, handnum),
+            (_, Outcome::EvenMoney) => format!("{} was paid even money", name),
+            // Dealer has a natural blackjack.  Everyone loses except for anyone with a natural blackjack.
+            (HandType::Natural, Outcome::Push) if insurance > Rational64::zero() => format!(
+                "Tie. Dealer and {} have Blackjacks. Insurance pays {:.2}",
+                name, insurance
+            ),
+            (HandType::Natural, Outcome::Push) => {
+                format!("Tie. Dealer and {} have Blackjacks", name)
             }
-        }
-        self.next_player()
+            (HandType::Natural, _) if insurance > Rational64::zero() => format!(
+                "Dealer has a blackjack. Insurance pays {} {:.2}",
+                name, insurance
+            ),
+            (HandType::Natural, _) => format!("Dealer has a blackjack, {} lost", name),
+            (_, Outcome::Bust) => format!("{}{} Busts", name, handnum),
+            (_, Outcome::Blackjack) => format!("{}{} Blackjack!", name, handnum),
+            (HandType::Bust, _) => format!(
+                "{}{} won! Player score: {}, Dealer Bust",
+                name, handnum, player_score,
+            ),
+            (_, Outcome::Win) => format!(
+                "{}{} won! Player score: {}, Dealer score {}",
+                name, handnum, player_score, dealer_score
+            ),
+            (_, Outcome::Push) => format!(
+                "{}{} Tie! Player score: {}, Dealer score {}",
+                name, handnum, player_score, dealer_score
+            ),
+            (_, Outcome::Lose) => format!(
+                "Sorry, {}{} lost. Player score: {}, Dealer score {}",
+                name, handnum, player_score, dealer_score
+            ),
+        };
+        self.message.set_label(&message);
+
+        self.players_gui[player].set_bet("0");
+        self.players_gui[player].set_insurance("0");
+        self.update_points(player);
     }
 
-    fn next_player(&mut self) -> Option<()> {
-        if let Some((i, _)) = self
-            .table
-            .player_iter()
-            .enumerate()
-            .skip(self.index + 1)
-            .find(|(_, p)| p.borrow().status() == Status::Playing)
-        {
-            self.index = i;
-            Some(())
-        } else {
-            None
-        }
+    fn update_points(&mut self, player: usize) {
+        let money = self
+            .engine
+            .table()
+            .player(player)
+            .unwrap()
+            .borrow()
+            .display_money();
+        self.players_gui[player].set_points(&money);
     }
 
-    fn next_hand(&mut self) -> Option<()> {
-        let player = Rc::clone(&self.active_players[self.index]);
-        if player.borrow().num_hands() > self.hand_num + 1 {
-            self.hand_num += 1;
-            Some(())
-        } else {
-            None
+    pub fn set_current(&mut self, index: usize) {
+        for player in self.players_gui.iter_mut() {
+            player.deactivate_player();
         }
+        let player = &mut self.players_gui[index];
+        player.activate_player();
     }
 
-    fn is_bust(&self) -> bool {
-        let player = Rc::clone(&self.active_players[self.index]);
-        let handtype = player.borrow().get_hand(self.hand_num).unwrap().hand_type();
-        handtype == HandType::Bust
+    fn hand(&self, player: usize, hand: usize) -> Hand {
+        self.engine
+            .table()
+            .player(player)
+            .unwrap()
+            .borrow()
+            .get_hand(hand)
+            .unwrap()
+            .clone()
     }
 
-    fn player_name(&self) -> String {
-        self.table
-            .player(self.index)
+    fn player_name(&self, index: usize) -> String {
+        self.engine
+            .table()
+            .player(index)
             .unwrap()
-            .clone()
             .borrow()
             .name()
             .clone()
     }
 
-    fn game_over(&mut self) {
-        self.middle.hide_buttons();
-        self.message
-            .set_label("Game over!  All players are out of money.");
-    }
-
     // todo allow the player section to show completed hands
     #[allow(dead_code)]
-    fn player_hand_below(&mut self) {
-        let thegui = &self.players_gui[self.index];
+    fn player_hand_below(&mut self, index: usize) {
+        let thegui = &self.players_gui[index];
         for (_, hand) in self
-            .table
-            .player(self.index)
+            .engine
+            .table()
+            .player(index)
             .unwrap()
             .borrow()
             .hand_iter()
@@ -783,3 +761,25 @@ impl GUIMain {
         }
     }
 }
+
+/// Asks for a count until one is given.  `None` if the player cancels.
+fn ask_count(prompt: &str) -> Option<Rational64> {
+    let mut prompt = prompt.to_string();
+    loop {
+        let answer = dialog::input_default(&prompt, "")?;
+        match parse_count(&answer) {
+            Some(count) => return Some(count),
+            None => {
+                prompt = format!(
+                    "{} is not a count.  Enter a number such as -2 or 1.5",
+                    answer
+                )
+            }
+        }
+    }
+}
+
+/// A count as it is said, such as "-2" or "1.5".
+pub(crate) fn show_count(count: Rational64) -> String {
+    count.to_f64().unwrap_or(0.).to_string()
+}
@@ -1,12 +1,16 @@
 use crate::card::Denomination::*;
-use crate::card::{BlackJackScore, Card, Denomination, Visible};
+use crate::card::{BlackJackScore, Card, Denomination, Suit, Visible};
 use crate::constants::{DOUBLECARDCOUNT, SPLITCARDCOUNT, TWENTYONE};
 use crate::errors::BlJaError;
+use crate::rules::{SurrenderRule, TableRules};
 use crate::Res;
 use num::Rational64;
 use std::collections::HashSet;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
 
 #[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum HandType {
     Normal,      // normal hand
     Soft,        // 'soft', as in a Ace is present and 11, not one is present
@@ -17,17 +21,22 @@ pub enum HandType {
     Doubled,     // The hand has been doubled down on
     DoubledSoft, // Doubled with ace
     Bust,        // Busted.
+    Surrendered, // Given up for half of the bet
+    EvenMoney,   // Natural paid 1:1 against a dealer's ace
 }
 
 #[derive(Debug, PartialEq, Eq, Copy, Clone, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Action {
     Hit,
     Stand,
     Split,
     Double,
+    Surrender,
 }
 
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Hand {
     cards: Vec<Visible<Card>>,
     htype: HandType,
@@ -83,27 +92,20 @@ impl Hand {
     }
 
     /// Action to add any flipped over card to the score and adjust the handtype
-    #[allow(mutable_borrow_reservation_conflict)]
     pub fn flip_over(&mut self) -> HandType {
         // Only the last card in the hand should be faced down.
-        if self.cards.len() == 0 {
-            return self.htype;
-        }
-        if let Some(last) = self.cards.get(self.cards.len() - 1) {
-            match last {
-                Visible::FacedUp(_) => self.htype, // already face up
-                Visible::FacedDown(c) => {
-                    last.flip_up();
-                    self.add_card_to_score(*c)
-                }
+        match self.cards.last_mut() {
+            Some(last) if last.is_faced_down() => {
+                *last = last.flip_up();
+                let card = **last;
+                self.add_card_to_score(card)
             }
-        } else {
-            self.htype
+            _ => self.htype, // already face up
         }
     }
 
-    /// Get the actions available for the hand
-    pub fn actions(&self) -> HashSet<Action> {
+    /// Get the actions available for the hand under the table's rules
+    pub fn actions(&self, rules: &TableRules) -> HashSet<Action> {
         use HandType::*;
 
         match self.htype {
@@ -114,14 +116,31 @@ impl Hand {
                 if self.splitable() {
                     set.insert(Action::Split);
                 }
-                if self.doubleable() {
+                if self.doubleable() && rules.allows_double(self) {
                     set.insert(Action::Double);
                 }
+                if self.surrenderable() && rules.surrender != SurrenderRule::NotAllowed {
+                    set.insert(Action::Surrender);
+                }
                 set.insert(Action::Hit);
                 set.insert(Action::Stand);
                 set
             }
-            _ => HashSet::with_capacity(0), // Bust, Natural, Doubled<...>, SplitAces
+            // Split aces only get one card each, unless the table allows them to be split again or hit.
+            SplitAces if self.cards.len() == SPLITCARDCOUNT => {
+                let mut set = HashSet::with_capacity(3);
+                if rules.resplit_aces && self.splitable() {
+                    set.insert(Action::Split);
+                }
+                if rules.hit_split_aces {
+                    set.insert(Action::Hit);
+                }
+                if !set.is_empty() {
+                    set.insert(Action::Stand);
+                }
+                set
+            }
+            _ => HashSet::with_capacity(0), // Bust, Natural, Doubled<...>, SplitAces, Surrendered, EvenMoney
         }
     }
 
@@ -180,6 +199,38 @@ impl Hand {
         Ok(Doubled(self.clone()))
     }
 
+    /// Gives up the hand, returning the half of the bet that is refunded.
+    pub fn surrender(&mut self) -> Res<Rational64> {
+        if !self.surrenderable() {
+            return Err(Box::new(BlJaError::ImproperAction(
+                "Can only surrender the first two cards of an unsplit hand",
+            )));
+        }
+        let refund = self.bet.ok_or("Surrendering a hand that has no bet")? / 2;
+        self.htype = HandType::Surrendered;
+        Ok(refund)
+    }
+
+    /// Pays out a natural at 1:1, returning the bet and winnings.
+    pub fn even_money(&mut self) -> Res<Rational64> {
+        if self.htype != HandType::Natural {
+            return Err(Box::new(BlJaError::ImproperAction(
+                "Even money is only paid on a natural",
+            )));
+        }
+        let payout = self.bet.ok_or("Even money on a hand that has no bet")? * 2;
+        self.htype = HandType::EvenMoney;
+        Ok(payout)
+    }
+
+    /// Only the first two cards of a hand that has not been split can be surrendered.
+    pub fn surrenderable(&self) -> bool {
+        match self.htype {
+            HandType::Normal | HandType::Soft => self.cards.len() == 2,
+            _ => false,
+        }
+    }
+
     fn splitable(&self) -> bool {
         if self.cards.len() == SPLITCARDCOUNT {
             if let Some(first) = self.cards.get(0) {
@@ -231,8 +282,16 @@ impl Hand {
     /// Local function that does the grunt work to add the card to the score and adjust the handtype.
     fn add_card_to_score(&mut self, card: Card) -> HandType {
         use HandType::*;
-        // Get the score
-        let cardscore = card.score();
+        // A split ace that is hit plays on like any other soft split hand
+        if self.htype == SplitAces && self.cards.len() > SPLITCARDCOUNT {
+            self.htype = SplitSoft;
+        }
+
+        // Get the score. Only one ace can count as 11, so any more aces drawn to a soft hand count as 1.
+        let cardscore = match self.htype {
+            Soft | SplitSoft | DoubledSoft if card.denom() == Ace => 1,
+            _ => card.score(),
+        };
 
         // Upgrade to a 'soft' hand if not already
         if card.denom() == Ace {
@@ -272,6 +331,11 @@ impl Hand {
                     self.score -= 10;
                     Doubled
                 }
+                // The split ace is still counted as 11 when a second ace is drawn to it
+                SplitAces if self.cards.len() == SPLITCARDCOUNT => {
+                    self.score -= 10;
+                    SplitAces
+                }
                 x => x,
             };
             // if still > 21, bust
@@ -296,10 +360,7 @@ impl Hand {
     }
 
     pub fn is_first_card_ace(&self) -> bool {
-        if self.cards.len() < 2 {
-            return false;
-        }
-        self.cards[0].is_ace()
+        matches!(self.cards.first(), Some(c) if c.is_ace())
     }
 
     pub fn peek_for_natural(&self) -> bool {
@@ -311,6 +372,62 @@ impl Hand {
     }
 }
 
+/// Shows the cards separated by commas, such as "A \u{2660}, 7 \u{2661}".
+impl Display for Hand {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let cards: Vec<String> = self.cards.iter().map(|c| c.to_string()).collect();
+        write!(f, "{}", cards.join(", "))
+    }
+}
+
+/// Parses cards separated by commas, such as "A,7", "AS, 7H", or the format shown by `Display`.  Cards
+/// without a suit are spades.  Two card hands can also be described as "soft 18", "hard 10" or "pair 8".
+/// Soft and hard totals are never pairs, so "soft 12" and "hard 4", which can only be two aces or two twos,
+/// are errors and are written "pair A" and "pair 2".  "hard 20" is a king and a ten.
+impl FromStr for Hand {
+    type Err = BlJaError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || BlJaError::InvalidHand(s.to_string());
+        let lower = s.trim().to_ascii_lowercase();
+        let mut words = lower.split_whitespace();
+
+        let denoms: Vec<Denomination> = match (words.next(), words.next(), words.next()) {
+            (Some("pair"), Some(value), None) => {
+                let denom = value.parse().map_err(|_| invalid())?;
+                vec![denom, denom]
+            }
+            (Some(kind), Some(total), None) if kind == "soft" || kind == "hard" => {
+                let total: usize = total.parse().map_err(|_| invalid())?;
+                match (kind, total) {
+                    ("soft", 13..=21) => vec![Ace, Numerical(total - 11)],
+                    ("hard", 5..=11) => vec![Numerical(2), Numerical(total - 2)],
+                    ("hard", 12..=19) => vec![Numerical(10), Numerical(total - 10)],
+                    ("hard", 20) => vec![King, Numerical(10)],
+                    _ => return Err(invalid()),
+                }
+            }
+            _ => {
+                let mut hand = Hand::new();
+                for card in s.split(',') {
+                    let card = card
+                        .parse()
+                        .or_else(|_| card.trim().parse().map(|d| Card::new(d, Suit::Spades)))
+                        .map_err(|_| invalid())?;
+                    hand.insert(Visible::FacedUp(card));
+                }
+                return Ok(hand);
+            }
+        };
+
+        let mut hand = Hand::new();
+        for denom in denoms {
+            hand.insert(Visible::FacedUp(Card::new(denom, Suit::Spades)));
+        }
+        Ok(hand)
+    }
+}
+
 #[cfg(test)]
 mod handtests {
     use super::*;
@@ -325,7 +442,7 @@ mod handtests {
     #[test]
     fn handtest() -> Res<()> {
         let mut hand = Hand::new();
-        let mut deck = Deck::new(1)?;
+        let mut deck: Deck = "2D 3D".parse()?;
         hand.insert(deck.deal(true));
         assert_eq!(hand.score, 2);
         assert_eq!(hand.htype, HandType::Normal);
@@ -339,6 +456,11 @@ mod handtests {
         hand.flip_over();
         assert_eq!(hand.score, 5);
         assert_eq!(hand.htype, HandType::Normal);
+        assert!(hand.card_iter().all(Visible::is_faced_up));
+
+        // The card stays face up, so it is only scored once
+        hand.flip_over();
+        assert_eq!(hand.score, 5);
 
         let king = Card::new(Denomination::King, Suit::Hearts);
         let queen = Card::new(Denomination::Queen, Suit::Spades);
@@ -377,12 +499,26 @@ mod handtests {
         assert_eq!(hand.score, 11);
         assert_eq!(hand.htype, HandType::Soft);
 
+        // One ace is still counted as 11
         hand.insert(Visible::FacedUp(ace2));
         assert_eq!(hand.score, 12);
-        assert_eq!(hand.htype, HandType::Normal);
+        assert_eq!(hand.htype, HandType::Soft);
 
         hand.insert(Visible::FacedUp(nine));
         assert_eq!(hand.score, 21);
+        assert_eq!(hand.htype, HandType::Soft);
+
+        // Both aces are counted as 1
+        hand.insert(Visible::FacedUp(king));
+        assert_eq!(hand.score, 21);
+        assert_eq!(hand.htype, HandType::Normal);
+
+        // Two aces and a ten card are 12, not a bust
+        let mut hand = Hand::new();
+        hand.insert(Visible::FacedUp(ace1));
+        hand.insert(Visible::FacedUp(ace2));
+        hand.insert(Visible::FacedUp(king));
+        assert_eq!(hand.score, 12);
         assert_eq!(hand.htype, HandType::Normal);
 
         Ok(())
@@ -391,6 +527,7 @@ mod handtests {
     #[test]
     fn actions() -> Res<()> {
         use Action::*;
+        let rules = TableRules::default();
 
         let mut hand = Hand::new();
 
@@ -398,7 +535,7 @@ mod handtests {
         let three = Card::new(Denomination::Numerical(3), Suit::Hearts);
 
         //empty hand
-        let actions = hand.actions();
+        let actions = hand.actions(&rules);
         assert!(actions.contains(&Hit));
         assert!(actions.contains(&Stand));
         assert_eq!(actions.len(), 2);
@@ -406,7 +543,7 @@ mod handtests {
         hand.insert(Visible::FacedUp(nine.clone()));
 
         // One card
-        let actions = hand.actions();
+        let actions = hand.actions(&rules);
         assert!(actions.contains(&Hit));
         assert!(actions.contains(&Stand));
         assert_eq!(actions.len(), 2);
@@ -415,7 +552,7 @@ mod handtests {
 
         // add same card (possible in multiple decks), check split avail
         hand.insert(Visible::FacedUp(nine.clone()));
-        let actions = hand.actions();
+        let actions = hand.actions(&rules);
         assert!(actions.contains(&Hit));
         assert!(actions.contains(&Stand));
         assert!(actions.contains(&Split));
@@ -426,7 +563,7 @@ mod handtests {
 
         // Add a three card.  Make sure 21 is score, no actions available, and normal type.
         hand.insert(Visible::FacedUp(three.clone()));
-        let actions = hand.actions();
+        let actions = hand.actions(&rules);
         assert_eq!(actions.len(), 0);
         assert_eq!(hand.score(), 21);
         assert_eq!(hand.hand_type(), HandType::Normal);
@@ -439,6 +576,7 @@ mod handtests {
         /*
         Reset hand and try other combos
          */
+        let rules = TableRules::default();
 
         let jack = Card::new(Denomination::Jack, Suit::Spades);
         let ace1 = Card::new(Denomination::Ace, Suit::Hearts);
@@ -453,12 +591,12 @@ mod handtests {
         // Check that split aces can form a natural.
         splitaces.insert(FacedUp(jack));
         assert_eq!(splitaces.hand_type(), HandType::Natural);
-        let actions = splitaces.actions();
+        let actions = splitaces.actions(&rules);
         assert_eq!(actions.len(), 0);
 
         splitaces2.insert(FacedUp(three));
         assert_eq!(splitaces2.hand_type(), SplitAces);
-        let actions = splitaces2.actions();
+        let actions = splitaces2.actions(&rules);
         assert_eq!(actions.len(), 0);
         assert_eq!(splitaces2.score(), 14);
 
@@ -468,6 +606,7 @@ mod handtests {
     #[test]
     // Test for actions with Ace in the first set of cards
     fn actions3() -> Res<()> {
+        let rules = TableRules::default();
         let queen = Card::new(Denomination::Queen, Suit::Spades);
         let ace1 = Card::new(Denomination::Ace, Suit::Hearts);
         let three = Card::new(Denomination::Numerical(3), Suit::Hearts);
@@ -478,7 +617,7 @@ mod handtests {
         hand.insert(FacedUp(ace1));
         assert_eq!(hand.score(), 14);
         assert_eq!(hand.hand_type(), HandType::Soft);
-        let actions = hand.actions();
+        let actions = hand.actions(&rules);
         assert_eq!(actions.len(), 3);
         assert!(actions.contains(&Hit));
         assert!(actions.contains(&Stand));
@@ -488,7 +627,7 @@ mod handtests {
         hand.insert(FacedUp(queen));
         assert_eq!(hand.score(), 14);
         assert_eq!(hand.hand_type(), HandType::Normal);
-        let actions = hand.actions();
+        let actions = hand.actions(&rules);
         assert_eq!(actions.len(), 2);
         assert!(actions.contains(&Hit));
         assert!(actions.contains(&Stand));
@@ -497,7 +636,7 @@ mod handtests {
         hand.insert(FacedUp(nine));
         assert_eq!(hand.score(), 23);
         assert_eq!(hand.hand_type(), HandType::Bust);
-        let actions = hand.actions();
+        let actions = hand.actions(&rules);
         assert_eq!(actions.len(), 0);
 
         Ok(())
@@ -526,6 +665,7 @@ mod handtests {
     #[test]
     fn double() -> Res<()> {
         use HandType::*;
+        let rules = TableRules::default();
 
         let mut hand = Hand::new();
         hand.set_bet(100.into());
@@ -544,7 +684,7 @@ mod handtests {
 
         assert_eq!(hand.score(), 17);
         assert_eq!(hand.hand_type(), Doubled);
-        let actions = hand.actions();
+        let actions = hand.actions(&rules);
         assert_eq!(actions.len(), 0);
         assert_eq!(hand.bet().unwrap(), 200.into());
 
@@ -558,7 +698,7 @@ mod handtests {
         let hand = hand.double()?.insert(FacedUp(nine));
         assert_eq!(hand.score(), 14);
         assert_eq!(hand.hand_type(), Doubled);
-        let actions = hand.actions();
+        let actions = hand.actions(&rules);
         assert_eq!(actions.len(), 0);
         assert_eq!(hand.bet().unwrap(), 250.into());
 
@@ -567,6 +707,7 @@ mod handtests {
 
     #[test]
     fn split() -> Res<()> {
+        let rules = TableRules::default();
         let queen = FacedUp(Card::new(Denomination::Queen, Suit::Spades));
         let ace1 = FacedUp(Card::new(Denomination::Ace, Suit::Hearts));
         let three = FacedUp(Card::new(Denomination::Numerical(3), Suit::Hearts));
@@ -585,13 +726,13 @@ mod handtests {
         assert_eq!(hand1s.hand_type(), HandType::Split);
         assert_eq!(hand2s.hand_type(), HandType::SplitSoft);
 
-        let actions = hand1s.actions();
+        let actions = hand1s.actions(&rules);
         assert_eq!(actions.len(), 3);
         assert!(actions.contains(&Hit));
         assert!(actions.contains(&Stand));
         assert!(actions.contains(&Double));
 
-        let actions = hand2s.actions();
+        let actions = hand2s.actions(&rules);
         assert_eq!(actions.len(), 3);
         assert!(actions.contains(&Hit));
         assert!(actions.contains(&Stand));
@@ -606,9 +747,219 @@ mod handtests {
         let hand = hand2s.double()?.insert(nine);
         assert_eq!(hand.hand_type(), HandType::Doubled);
         assert_eq!(hand.score(), 13);
-        let actions = hand.actions();
+        let actions = hand.actions(&rules);
         assert_eq!(actions.len(), 0);
 
         Ok(())
     }
+
+    #[test]
+    fn split_aces() -> Res<()> {
+        use HandType::*;
+
+        let ace1 = FacedUp(Card::new(Denomination::Ace, Suit::Hearts));
+        let ace2 = FacedUp(Card::new(Denomination::Ace, Suit::Spades));
+        let ace3 = FacedUp(Card::new(Denomination::Ace, Suit::Clubs));
+        let nine = FacedUp(Card::new(Denomination::Numerical(9), Suit::Diamonds));
+
+        let mut hand = Hand::new();
+        hand.set_bet(100.into());
+        hand.insert(ace1);
+        hand.insert(ace2);
+        let (hand1, hand2) = hand.split_hand()?;
+        let hand1 = hand1.insert(ace3);
+        let hand2 = hand2.insert(nine);
+
+        // Drawing an ace to a split ace is 12, not a bust
+        assert_eq!(hand1.hand_type(), SplitAces);
+        assert_eq!(hand1.score(), 12);
+        assert_eq!(hand2.hand_type(), SplitAces);
+        assert_eq!(hand2.score(), 20);
+
+        let mut rules = TableRules::default();
+        assert_eq!(hand1.actions(&rules).len(), 0);
+
+        rules.resplit_aces = true;
+        let actions = hand1.actions(&rules);
+        assert_eq!(actions.len(), 2);
+        assert!(actions.contains(&Action::Split));
+        assert!(actions.contains(&Stand));
+        assert_eq!(hand2.actions(&rules).len(), 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn hit_split_aces() -> Res<()> {
+        let ace1 = FacedUp(Card::new(Denomination::Ace, Suit::Hearts));
+        let ace2 = FacedUp(Card::new(Denomination::Ace, Suit::Spades));
+        let ace3 = FacedUp(Card::new(Denomination::Ace, Suit::Clubs));
+        let five = FacedUp(Card::new(Denomination::Numerical(5), Suit::Diamonds));
+        let king = FacedUp(Card::new(Denomination::King, Suit::Diamonds));
+
+        let mut hand = Hand::new();
+        hand.set_bet(100.into());
+        hand.insert(ace1);
+        hand.insert(ace2);
+        let (hand1, hand2) = hand.split_hand()?;
+        let mut hand1 = hand1.insert(five);
+        let mut hand2 = hand2.insert(ace3);
+
+        let mut rules = TableRules {
+            hit_split_aces: true,
+            ..TableRules::default()
+        };
+        let actions = hand1.actions(&rules);
+        assert_eq!(actions.len(), 2);
+        assert!(actions.contains(&Action::Hit));
+        assert!(actions.contains(&Action::Stand));
+        assert_eq!(hand2.actions(&rules).len(), 2);
+
+        // Both options together
+        rules.resplit_aces = true;
+        assert_eq!(hand1.actions(&rules).len(), 2);
+        assert_eq!(hand2.actions(&rules).len(), 3);
+
+        // Hit cards count the ace as 1 once 21 is passed
+        assert_eq!(hand1.insert(five), HandType::SplitSoft);
+        assert_eq!(hand1.score(), 21);
+        assert_eq!(hand1.insert(king), HandType::Split);
+        assert_eq!(hand1.score(), 21);
+        assert_eq!(hand2.insert(king), HandType::Split);
+        assert_eq!(hand2.score(), 12);
+        assert!(!hand2.actions(&rules).contains(&Action::Double));
+        assert!(!hand2.actions(&rules).contains(&Action::Split));
+
+        Ok(())
+    }
+
+    #[test]
+    fn double_after_split() -> Res<()> {
+        let queen = FacedUp(Card::new(Denomination::Queen, Suit::Spades));
+        let three = FacedUp(Card::new(Denomination::Numerical(3), Suit::Hearts));
+        let three2 = FacedUp(Card::new(Denomination::Numerical(3), Suit::Diamonds));
+        let eight = FacedUp(Card::new(Denomination::Numerical(8), Suit::Diamonds));
+
+        let mut hand = Hand::new();
+        hand.set_bet(100.into());
+        hand.insert(three);
+        hand.insert(three2);
+        let (hand1, _) = hand.split_hand()?;
+        let hand1 = hand1.insert(eight);
+        assert_eq!(hand1.score(), 11);
+
+        let mut rules = TableRules::default();
+        assert!(hand1.actions(&rules).contains(&Double));
+
+        rules.double_after_split = false;
+        let actions = hand1.actions(&rules);
+        assert_eq!(actions.len(), 2);
+        assert!(!actions.contains(&Double));
+
+        // Unsplit hands can still double
+        let mut hand = Hand::new();
+        hand.insert(three);
+        hand.insert(queen);
+        assert!(hand.actions(&rules).contains(&Double));
+
+        Ok(())
+    }
+
+    #[test]
+    fn surrender() -> Res<()> {
+        let ten = FacedUp(Card::new(Denomination::Numerical(10), Suit::Clubs));
+        let six = FacedUp(Card::new(Denomination::Numerical(6), Suit::Hearts));
+        let two = FacedUp(Card::new(Denomination::Numerical(2), Suit::Hearts));
+
+        let mut hand = Hand::new();
+        hand.set_bet(25.into());
+        hand.insert(ten);
+        hand.insert(six);
+
+        let mut rules = TableRules::default();
+        assert!(!hand.actions(&rules).contains(&Action::Surrender));
+        rules.surrender = SurrenderRule::Late;
+        assert!(hand.actions(&rules).contains(&Action::Surrender));
+        rules.surrender = SurrenderRule::Early;
+        assert!(hand.actions(&rules).contains(&Action::Surrender));
+
+        // Not available after a third card
+        let mut hit = hand.clone();
+        hit.insert(two);
+        assert!(!hit.actions(&rules).contains(&Action::Surrender));
+        assert!(hit.surrender().is_err());
+
+        assert_eq!(hand.surrender()?, Rational64::new(25, 2));
+        assert_eq!(hand.hand_type(), HandType::Surrendered);
+        assert_eq!(hand.actions(&rules).len(), 0);
+        assert!(hand.surrender().is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn parse() -> Res<()> {
+        let hand: Hand = "A,7".parse()?;
+        assert_eq!(hand.num_cards(), 2);
+        assert_eq!((hand.score(), hand.hand_type()), (18, HandType::Soft));
+        assert_eq!(hand, "soft 18".parse()?);
+        assert_eq!(hand, " As , 7s".parse()?);
+
+        let hand: Hand = "10H, 6\u{2663}, 5".parse()?;
+        assert_eq!((hand.score(), hand.hand_type()), (21, HandType::Normal));
+        assert_eq!(hand.to_string(), "10 \u{2661}, 6 \u{2663}, 5 \u{2660}");
+        assert_eq!(hand.to_string().parse::<Hand>()?, hand);
+
+        // Every soft and hard total, and pair
+        let rules = TableRules::default();
+        for total in 13..=21 {
+            let hand: Hand = format!("soft {}", total).parse()?;
+            assert!(!hand.actions(&rules).contains(&Action::Split));
+            assert_eq!(hand.score(), total);
+            let expected = if total == 21 {
+                HandType::Natural
+            } else {
+                HandType::Soft
+            };
+            assert_eq!(hand.hand_type(), expected);
+        }
+        for total in 5..=20 {
+            let hand: Hand = format!("Hard {}", total).parse()?;
+            assert_eq!((hand.score(), hand.hand_type()), (total, HandType::Normal));
+            assert_eq!(hand.num_cards(), 2);
+            assert!(!hand.actions(&rules).contains(&Action::Split));
+        }
+        assert!(!"hard 20"
+            .parse::<Hand>()?
+            .actions(&rules)
+            .contains(&Action::Split));
+        let eights: Hand = "pair 8".parse()?;
+        assert!(eights.actions(&rules).contains(&Action::Split));
+        assert_eq!(eights.score(), 16);
+        assert_eq!("pair a".parse::<Hand>()?.score(), 12);
+
+        for bad in [
+            "",
+            "A,",
+            "A,,7",
+            "soft 11",
+            "soft 12",
+            "soft 22",
+            "hard 3",
+            "hard 4",
+            "hard 21",
+            "pair 1",
+            "soft",
+            "A,X",
+            "soft 18 A",
+        ]
+        .iter()
+        {
+            assert_eq!(
+                bad.parse::<Hand>(),
+                Err(BlJaError::InvalidHand(bad.to_string()))
+            );
+        }
+        Ok(())
+    }
 }
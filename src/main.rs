@@ -8,22 +8,48 @@ use fltk::{
 };
 use std::error::Error;
 
+use crate::config::{Config, DEFAULT_CONFIG};
+use crate::counting::{Accuracy, CountingSystem, HI_LO};
+use crate::deck::Deck;
+use crate::engine::Engine;
 use crate::gui_classes::*;
 use crate::hand::Action;
+use crate::player::{parse_money, Player};
+use crate::rules::{CutCard, DealingMode, DoubleRule, SurrenderRule, TableRules};
 use crate::table::Table;
 use clap::{App, Arg};
+#[cfg(feature = "serde")]
+use fltk::dialog::{FileDialog, FileDialogOptions, FileDialogType};
 use gui_classes::middle::*;
 use gui_classes::player_widget::GUIPlayer;
+use num::Rational64;
+#[cfg(feature = "serde")]
+use profile::{ProfileStore, Profiles};
+use rand::SeedableRng;
+use rand_chacha::ChaCha12Rng;
+#[cfg(feature = "serde")]
+use std::path::Path;
+use std::path::PathBuf;
 use std::process::exit;
 
 mod card;
+mod config;
 mod constants;
+mod counting;
 mod deck;
 mod deck_traits;
+mod engine;
 mod errors;
 mod gui_classes;
 mod hand;
 mod player;
+#[cfg(feature = "serde")]
+mod profile;
+mod review;
+mod rules;
+#[cfg(feature = "serde")]
+mod save;
+mod strategy;
 mod table;
 
 // Type alias for Result<T, Box<dyn Error>>
@@ -34,9 +60,16 @@ pub enum Message {
     CurrentPlayer(usize),
     Bet(String),
     Insurance(String),
+    EvenMoney,
     Play(Action),
     Restart,
     Continue,
+    Save,
+    Load,
+    Sit,
+    Count,
+    Hint,
+    Review,
 }
 
 fn main() -> Res<()> {
@@ -53,6 +86,27 @@ fn main() -> Res<()> {
                 .takes_value(true)
                 .default_value("4"),
         )
+        .arg(
+            Arg::with_name("names")
+                .help("Names the players in seat order, separated by commas. Sets the number of players if -p is not given")
+                .short("n")
+                .long("names")
+                .takes_value(true)
+                .use_delimiter(true),
+        )
+        .arg(
+            Arg::with_name("bankroll")
+                .help("Sets the points each player starts with, such as 500 or 250.50")
+                .long("bankroll")
+                .takes_value(true)
+                .default_value("500"),
+        )
+        .arg(
+            Arg::with_name("config")
+                .help("Reads the table settings from this file. blackjack.toml is read if it is in the working directory")
+                .long("config")
+                .takes_value(true),
+        )
         .arg(
             Arg::with_name("decks")
                 .help("Sets the amount of 52 card decks used. Minimum of 1, Maxmimum of 8")
@@ -61,14 +115,343 @@ fn main() -> Res<()> {
                 .takes_value(true)
                 .default_value("6"),
         )
+        .arg(
+            Arg::with_name("seed")
+                .help("Seeds the shuffles, so that the same seed deals the same cards")
+                .long("seed")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("stack")
+                .help("Deals these cards first, in order, such as \"8S 8H AS 8D 8C KD\". Shuffled shoes follow")
+                .long("stack")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("load")
+                .help("Resumes a saved game. The players, shoe and rules are taken from the save")
+                .long("load")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("min-bet")
+                .help("Sets the table minimum bet, such as 5 or 2.50. Players are out once they cannot cover it")
+                .long("min-bet")
+                .takes_value(true)
+                .default_value("1"),
+        )
+        .arg(
+            Arg::with_name("max-bet")
+                .help("Sets the table maximum bet, such as 500 or 250.50. There is no maximum if not set")
+                .long("max-bet")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("payout")
+                .help("Sets the payout for a blackjack")
+                .long("payout")
+                .takes_value(true)
+                .possible_values(&["3:2", "6:5", "1:1"])
+                .default_value("3:2"),
+        )
+        .arg(
+            Arg::with_name("double")
+                .help("Sets which two card hands can be doubled: any two cards, or only hard 9 to 11 or 10 to 11")
+                .long("double")
+                .takes_value(true)
+                .possible_values(&["any", "9-11", "10-11"])
+                .default_value("any"),
+        )
+        .arg(
+            Arg::with_name("no-das")
+                .help("Does not allow doubling after a split")
+                .long("no-das"),
+        )
+        .arg(
+            Arg::with_name("das")
+                .help("Allows doubling after a split, the default")
+                .long("das")
+                .conflicts_with("no-das"),
+        )
+        .arg(
+            Arg::with_name("max-hands")
+                .help("Sets the most hands a player can hold from splitting. Minimum of 1, Maximum of 8")
+                .long("max-hands")
+                .takes_value(true)
+                .default_value("4"),
+        )
+        .arg(
+            Arg::with_name("resplit-aces")
+                .help("Allows split aces to be split again")
+                .long("resplit-aces"),
+        )
+        .arg(
+            Arg::with_name("no-resplit-aces")
+                .help("Does not allow split aces to be split again, the default")
+                .long("no-resplit-aces")
+                .conflicts_with("resplit-aces"),
+        )
+        .arg(
+            Arg::with_name("hit-split-aces")
+                .help("Allows split aces to be hit. Otherwise they only get one card each")
+                .long("hit-split-aces"),
+        )
+        .arg(
+            Arg::with_name("no-hit-split-aces")
+                .help("Split aces only get one card each, the default")
+                .long("no-hit-split-aces")
+                .conflicts_with("hit-split-aces"),
+        )
+        .arg(
+            Arg::with_name("surrender")
+                .help("Allows surrendering half of the bet, either only after the dealer checks for blackjack (late), or before as well (early)")
+                .long("surrender")
+                .takes_value(true)
+                .possible_values(&["none", "late", "early"]),
+        )
+        .arg(
+            Arg::with_name("penetration")
+                .help("How much of the shoe is dealt before reshuffling, as a percentage (75%) or a number of cards. Without it, shoes are cut 60 to 75 cards from the back and a single deck is reshuffled every round")
+                .long("penetration")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("jitter")
+                .help("Moves the cut card up to this many cards either way from the penetration")
+                .long("jitter")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("burn")
+                .help("Burns this many cards from the top of each new shoe, up to 10")
+                .long("burn")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("burn-face-up")
+                .help("Shows the burned cards in the discard tray. Otherwise they are burned face down")
+                .long("burn-face-up"),
+        )
+        .arg(
+            Arg::with_name("burn-face-down")
+                .help("Burns cards face down, the default")
+                .long("burn-face-down")
+                .conflicts_with("burn-face-up"),
+        )
+        .arg(
+            Arg::with_name("count")
+                .help("Counts the cards with this system: Hi-Lo, KO, Omega-II, Zen or Wong-Halves. The Count button shows the count [default: Hi-Lo]")
+                .long("count")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("trainer")
+                .help("Counting practice. Asks for the running and true count now and then before a round")
+                .long("trainer"),
+        )
+        .arg(
+            Arg::with_name("no-trainer")
+                .help("No counting practice, the default")
+                .long("no-trainer")
+                .conflicts_with("trainer"),
+        )
+        .arg(
+            Arg::with_name("h17")
+                .help("The dealer hits on a soft 17. Otherwise the dealer stands on all 17s")
+                .long("h17"),
+        )
+        .arg(
+            Arg::with_name("s17")
+                .help("The dealer stands on all 17s, the default")
+                .long("s17")
+                .conflicts_with("h17"),
+        )
+        .arg(
+            Arg::with_name("enhc")
+                .help("European no hole card. The dealer draws a second card only after the players finish")
+                .long("enhc"),
+        )
+        .arg(
+            Arg::with_name("hole-card")
+                .help("The dealer takes a hole card and checks it for blackjack, the default")
+                .long("hole-card")
+                .conflicts_with("enhc"),
+        )
+        .arg(
+            Arg::with_name("obo")
+                .help("With --enhc, a dealer blackjack only takes the original bet, returning doubles and splits")
+                .long("obo")
+                .requires("enhc"),
+        )
         .get_matches();
 
-    // Get options from CLI or use defaults
-    let players: usize = matches.value_of("players").unwrap_or_default().parse()?;
-    let decks: usize = matches.value_of("decks").unwrap_or_default().parse()?;
+    // Settings from the config file, which the command line options override
+    let config_path = match matches.value_of("config") {
+        Some(path) => Some(PathBuf::from(path)),
+        // Only builds that can read config files look for one
+        None => Some(PathBuf::from(DEFAULT_CONFIG))
+            .filter(|path| cfg!(feature = "serde") && path.is_file()),
+    };
+    let config = match &config_path {
+        Some(path) => match Config::read(path) {
+            Ok(config) => config,
+            Err(e) => {
+                eprintln!("{}: {}", path.display(), e);
+                exit(1);
+            }
+        },
+        None => Config::default(),
+    };
+    // Options given on the command line, as opposed to their defaults
+    let given = |name: &str| match matches.occurrences_of(name) {
+        0 => None,
+        _ => matches.value_of(name),
+    };
+    // Rules turned on or off on the command line, such as --h17 or --s17
+    let turned = |on: &str, off: &str| match (matches.is_present(on), matches.is_present(off)) {
+        (true, _) => Some(true),
+        (_, true) => Some(false),
+        _ => None,
+    };
+
+    // Get options from CLI, the config, or use defaults
+    let names: Vec<String> = match matches.values_of("names") {
+        Some(names) => names.map(|name| name.trim().to_string()).collect(),
+        None => config.names.clone().unwrap_or_default(),
+    };
+    // Naming the players is enough to seat them
+    let players: usize = match (given("players"), config.players) {
+        (Some(players), _) => players.parse()?,
+        _ if matches.is_present("names") => names.len(),
+        (None, Some(players)) => players,
+        (None, None) if !names.is_empty() => names.len(),
+        (None, None) => matches.value_of("players").unwrap_or_default().parse()?,
+    };
+    let bankroll = match (given("bankroll"), config.bankroll) {
+        (None, Some(bankroll)) => bankroll,
+        _ => parse_money(matches.value_of("bankroll").unwrap_or_default())?,
+    };
+    let decks: usize = match (given("decks"), config.decks) {
+        (None, Some(decks)) => decks,
+        _ => matches.value_of("decks").unwrap_or_default().parse()?,
+    };
+    let seed: Option<u64> = match matches.value_of("seed") {
+        Some(seed) => Some(seed.parse()?),
+        None => None,
+    };
+    let stack: Option<Deck> = match matches.value_of("stack") {
+        Some(cards) => Some(cards.parse()?),
+        None => None,
+    };
+    let system: CountingSystem = match (given("count"), config.count) {
+        (Some(system), _) => system.parse()?,
+        (None, Some(system)) => system,
+        (None, None) => HI_LO,
+    };
+    let trainer = match turned("trainer", "no-trainer")
+        .or(config.trainer)
+        .unwrap_or(false)
+    {
+        true => Some(Accuracy::default()),
+        false => None,
+    };
+
+    let mut rules = config.rules;
+    if let Some(min_bet) = given("min-bet") {
+        rules.min_bet = parse_money(min_bet)?;
+    }
+    if let Some(max_bet) = given("max-bet") {
+        rules.max_bet = Some(parse_money(max_bet)?);
+    }
+    if let Some(h17) = turned("h17", "s17") {
+        rules.dealer_hits_soft_17 = h17;
+    }
+    if let Some(payout) = given("payout") {
+        rules.blackjack_payout = match payout {
+            "6:5" => Rational64::new(6, 5),
+            "1:1" => Rational64::from_integer(1),
+            _ => Rational64::new(3, 2),
+        };
+    }
+    if let Some(double) = given("double") {
+        rules.double = match double {
+            "9-11" => DoubleRule::NineToEleven,
+            "10-11" => DoubleRule::TenToEleven,
+            _ => DoubleRule::AnyTwo,
+        };
+    }
+    if let Some(das) = turned("das", "no-das") {
+        rules.double_after_split = das;
+    }
+    if let Some(max_hands) = given("max-hands") {
+        rules.max_hands = max_hands.parse()?;
+    }
+    if let Some(resplit) = turned("resplit-aces", "no-resplit-aces") {
+        rules.resplit_aces = resplit;
+    }
+    if let Some(hit) = turned("hit-split-aces", "no-hit-split-aces") {
+        rules.hit_split_aces = hit;
+    }
+    if let Some(surrender) = given("surrender") {
+        rules.surrender = match surrender {
+            "late" => SurrenderRule::Late,
+            "early" => SurrenderRule::Early,
+            _ => SurrenderRule::NotAllowed,
+        };
+    }
+    match turned("enhc", "hole-card") {
+        Some(true) => {
+            rules.dealing = DealingMode::NoHoleCard {
+                original_bets_only: matches.is_present("obo"),
+            }
+        }
+        Some(false) => rules.dealing = DealingMode::HoleCard,
+        None => (),
+    }
+    if let Some(penetration) = given("penetration") {
+        let jitter = rules.cut_card.map_or(0, |cut_card| cut_card.jitter);
+        rules.cut_card = Some(CutCard {
+            penetration: penetration.parse()?,
+            jitter,
+        });
+    }
+    if let Some(burn) = given("burn") {
+        rules.burn = burn.parse()?;
+    }
+    if let Some(face_up) = turned("burn-face-up", "burn-face-down") {
+        rules.burn_face_up = face_up;
+    }
+    if let Some(jitter) = given("jitter") {
+        match &mut rules.cut_card {
+            Some(cut_card) => cut_card.jitter = jitter.parse()?,
+            None => {
+                eprintln!(
+                    "--jitter needs a --penetration. Run 'blackjack --help' for usage details"
+                );
+                exit(1);
+            }
+        }
+    }
+
+    // Players must be able to cover at least one bet
+    if rules.min_bet < Rational64::from_integer(1)
+        || rules.min_bet > bankroll
+        || matches!(rules.max_bet, Some(max) if max < rules.min_bet)
+    {
+        eprintln!("Invalid table limits or bankroll. Run 'blackjack --help' for usage details");
+        exit(1);
+    }
 
-    if players > 5 || players < 1 || decks < 1 || decks > 8 {
-        eprintln!("Invalid player or deck parameters. Run 'blackjack --help' for usage details");
+    if !(1..=5).contains(&players)
+        || names.len() > players
+        || names.iter().any(String::is_empty)
+        || !(1..=8).contains(&decks)
+        || !(1..=8).contains(&rules.max_hands)
+        || rules.burn > 10
+    {
+        eprintln!(
+            "Invalid player, deck, or hand parameters. Run 'blackjack --help' for usage details"
+        );
         exit(1);
     }
 
@@ -76,7 +459,30 @@ fn main() -> Res<()> {
     let (s, r) = app::channel::<Message>();
 
     // Table to be used for game.  Needed before the GUI is built so that the gui knows the players involved.
-    let table = Table::new(players, decks)?;
+    let (engine, players, decks, rules) = match matches.value_of("load") {
+        // Restarting keeps the loaded table's setup
+        #[cfg(feature = "serde")]
+        Some(path) => {
+            let engine = save::load(Path::new(path))?;
+            let (players, decks) = (engine.table().num_players(), engine.table().decks());
+            let rules = engine.table().rules().clone();
+            (engine, players, decks, rules)
+        }
+        #[cfg(not(feature = "serde"))]
+        Some(_) => {
+            return Err(Box::new(errors::BlJaError::InvalidSave(
+                "loading a saved game needs the serde feature".to_string(),
+            )))
+        }
+        None => (
+            Engine::new(new_table(
+                players, &names, bankroll, decks, &rules, seed, &stack,
+            )?),
+            players,
+            decks,
+            rules,
+        ),
+    };
 
     // Create the application.  All items between here and `wind.end()` are part of the gui.
     let app = app::App::default();
@@ -140,10 +546,24 @@ fn main() -> Res<()> {
     wind.make_resizable(false);
 
     // Merge all the created subsections above into one central control struct.
-    let mut gui = GUIMain::new(header, dealer, message, middle, playerwid, table);
+    let mut gui = GUIMain::new(header, dealer, message, middle, playerwid, engine);
+    gui.count_with(system, trainer);
+    gui.quiz_rng = quiz_rng(seed);
 
-    gui.setup_game();
-    gui.start_round();
+    // Profiles are optional, so the game is still played if there is nowhere to keep them
+    #[cfg(feature = "serde")]
+    let profile_store = ProfileStore::from_env().ok();
+    #[cfg(feature = "serde")]
+    {
+        gui.profiles = empty_seats(&profile_store, players, bankroll);
+    }
+
+    if matches.is_present("load") {
+        gui.resume();
+    } else {
+        gui.setup_game();
+        gui.start_round();
+    }
 
     wind.end();
     wind.show();
@@ -155,23 +575,144 @@ fn main() -> Res<()> {
                 Message::Play(action) => gui.perform_action(action),
                 Message::Continue => gui.continue_play(),
                 Message::Insurance(insurance_bet) => gui.set_insurance(insurance_bet),
+                Message::EvenMoney => gui.take_even_money(),
                 Message::Restart => {
-                    let table = Table::new(players, decks)?;
+                    // Counting practice and the review of plays carry on with the new game, but the
+                    // session so far is reviewed first
+                    if !gui.review.is_empty() {
+                        gui.show_review();
+                    }
+                    let trainer = gui.trainer;
+                    let review = gui.review;
+                    let table = new_table(players, &names, bankroll, decks, &rules, seed, &stack)?;
                     gui = GUIMain::new(
                         gui.header,
                         gui.dealer,
                         gui.message,
                         gui.middle,
                         gui.players_gui,
-                        table,
+                        Engine::new(table),
                     );
+                    gui.count_with(system, trainer);
+                    gui.quiz_rng = quiz_rng(seed);
+                    gui.review = review;
+                    #[cfg(feature = "serde")]
+                    {
+                        gui.profiles = empty_seats(&profile_store, players, bankroll);
+                    }
                     gui.setup_game();
                     gui.start_round();
                 }
+                #[cfg(feature = "serde")]
+                Message::Save => {
+                    if let Some(path) = choose_file(FileDialogType::BrowseSaveFile) {
+                        gui.save_game(&path);
+                    }
+                }
+                #[cfg(feature = "serde")]
+                Message::Load => {
+                    if let Some(path) = choose_file(FileDialogType::BrowseFile) {
+                        match save::load(&path) {
+                            // The player sections are built once, so the saved table must seat the same number
+                            Ok(engine) if engine.table().num_players() == gui.players_gui.len() => {
+                                gui.engine = engine;
+                                gui.count_with(system, gui.trainer);
+                                gui.profiles = empty_seats(&profile_store, players, bankroll);
+                                gui.resume();
+                            }
+                            Ok(engine) => gui.message.set_label(&format!(
+                                "The saved game has {} players, but this table has {}",
+                                engine.table().num_players(),
+                                gui.players_gui.len()
+                            )),
+                            Err(e) => gui.message.set_label(&format!("Could not load: {}", e)),
+                        }
+                    }
+                }
+                #[cfg(feature = "serde")]
+                Message::Sit => gui.pick_profile(),
+                Message::Count => gui.toggle_count(),
+                Message::Hint => gui.hint(),
+                Message::Review => gui.show_review(),
                 _ => println!("Other"),
             }
         }
     }
 
+    // The window has closed, ending the session
+    if !gui.review.is_empty() {
+        gui.show_review();
+    }
+
     Ok(())
 }
+
+/// Seats without profiles, if there is somewhere to save them.
+#[cfg(feature = "serde")]
+fn empty_seats(
+    store: &Option<ProfileStore>,
+    seats: usize,
+    bankroll: Rational64,
+) -> Option<Profiles> {
+    store
+        .as_ref()
+        .map(|store| Profiles::new(store.clone(), seats, bankroll))
+}
+
+/// Asks for a saved game file.  `None` if the dialog was cancelled.
+#[cfg(feature = "serde")]
+fn choose_file(kind: FileDialogType) -> Option<PathBuf> {
+    let mut chooser = FileDialog::new(kind);
+    chooser.set_filter("*.json");
+    chooser.set_option(FileDialogOptions::SaveAsConfirm);
+    chooser.show();
+    let path = chooser.filename();
+    if path.as_os_str().is_empty() {
+        None
+    } else {
+        Some(path)
+    }
+}
+
+/// Decides when the trainer asks for the count.  A seeded game uses its own stream of the seed, so a restart
+/// or another run with the same seed asks at the same points without changing the cards dealt.
+fn quiz_rng(seed: Option<u64>) -> ChaCha12Rng {
+    match seed {
+        Some(seed) => {
+            let mut rng = ChaCha12Rng::seed_from_u64(seed);
+            rng.set_stream(1);
+            rng
+        }
+        None => ChaCha12Rng::from_entropy(),
+    }
+}
+
+/// Creates the table, seeding its shuffles and stacking its first cards if given.  Restarting deals the same
+/// game again.
+fn new_table(
+    players: usize,
+    names: &[String],
+    bankroll: Rational64,
+    decks: usize,
+    rules: &TableRules,
+    seed: Option<u64>,
+    stack: &Option<Deck>,
+) -> Res<Table> {
+    let mut table = match seed {
+        Some(seed) => Table::with_rng(
+            Player::seats(players, names, bankroll),
+            decks,
+            rules.clone(),
+            ChaCha12Rng::seed_from_u64(seed),
+        )?,
+        None => Table::new(
+            Player::seats(players, names, bankroll),
+            decks,
+            rules.clone(),
+        )?,
+    };
+    if let Some(deck) = stack {
+        table.stack(deck.clone());
+    }
+    Ok(table)
+}
@@ -1,17 +1,21 @@
 use crate::card::{Card, Visible};
+use crate::constants::STARTING_MONEY;
 use crate::errors::BlJaError;
-use crate::hand::{Action, Hand};
+use crate::hand::{Action, Hand, HandType};
+use crate::rules::TableRules;
 use crate::Res;
-use num::{One, Rational64, ToPrimitive, Zero};
+use num::{Rational64, ToPrimitive, Zero};
 use std::collections::HashSet;
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Status {
     Playing,
     Out,
 }
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Player {
     name: String,
     hands: Vec<Hand>, // a player can have multiple hands following a split
@@ -23,16 +27,36 @@ pub struct Player {
 impl Player {
     /// Create a new player
     pub fn new(name: String) -> Player {
+        Player::with_money(name, Rational64::from_integer(STARTING_MONEY))
+    }
+
+    /// Create a new player bringing their own money to the table, such as a saved bankroll.
+    pub fn with_money(name: String, money: Rational64) -> Player {
         Player {
             name,
             hands: vec![Hand::new()],
-            money: Rational64::from_integer(500),
+            money,
             insurance: Rational64::zero(),
             status: Status::Playing,
         }
     }
 
-    pub fn place_bet(&mut self, bet: Rational64) -> Res<()> {
+    /// Players for the seats at a table, each bringing the same money.  Seats without a name are called
+    /// "Player N".
+    pub fn seats(count: usize, names: &[String], money: Rational64) -> Vec<Player> {
+        (0..count)
+            .map(|seat| {
+                let name = match names.get(seat) {
+                    Some(name) => name.clone(),
+                    None => format!("Player {}", seat + 1),
+                };
+                Player::with_money(name, money)
+            })
+            .collect()
+    }
+
+    /// Places the bet for the round, which must be within the table's limits.
+    pub fn place_bet(&mut self, bet: Rational64, rules: &TableRules) -> Res<()> {
         if self.hands.len() > 1
             || self
                 .hands
@@ -45,6 +69,7 @@ impl Player {
                 "Trying to place bet on a hand with cards or after split",
             )));
         }
+        rules.check_bet(bet)?;
         let newbalance = self.money - bet;
         if newbalance < Rational64::zero() {
             return Err(Box::new(BlJaError::NotEnoughMoney));
@@ -61,12 +86,13 @@ impl Player {
         if newbalance < Rational64::zero() {
             return Err(Box::new(BlJaError::NotEnoughMoney));
         }
-        let bet = self
-            .hands
-            .get(0)
-            .ok_or_else(|| "Player hand not initialized")?
-            .bet()
-            .ok_or_else(|| "Bet is not set")?;
+        let hand = self.hands.first().ok_or("Player hand not initialized")?;
+        if hand.hand_type() == HandType::EvenMoney {
+            return Err(Box::new(BlJaError::ImproperAction(
+                "Hand has already been paid even money",
+            )));
+        }
+        let bet = hand.bet().ok_or("Bet is not set")?;
         if insurance_bet * 2 as i64 > bet {
             return Err(Box::new(BlJaError::ExcessiveInsurance));
         }
@@ -76,13 +102,17 @@ impl Player {
         Ok(())
     }
 
-    /// Performs actions of splitting the hand, and adding cards to the hands in order.
+    /// Performs actions of splitting the hand, and adding cards to the hands in order.  The new hand's bet
+    /// must be within the table's limits.
     pub fn split_hand(
         &mut self,
         hand_num: usize,
         newcard1: Visible<Card>,
         newcard2: Visible<Card>,
-    ) {
+        rules: &TableRules,
+    ) -> Res<()> {
+        self.check_split(hand_num, rules)?;
+
         let (new1, new2) = self.hands.remove(hand_num).split_hand().unwrap();
         let new1 = new1.insert(newcard1);
         let new2 = new2.insert(newcard2);
@@ -93,23 +123,63 @@ impl Player {
         self.hands.insert(hand_num, new1);
 
         self.money -= bet;
+        Ok(())
+    }
+
+    /// Checks that the hand has a bet the player can match and the table allows, so it can be split.
+    pub fn check_split(&self, hand_num: usize, rules: &TableRules) -> Res<()> {
+        let bet = self
+            .hands
+            .get(hand_num)
+            .ok_or(BlJaError::InvalidIndex(hand_num))?
+            .bet()
+            .ok_or("Splitting a hand that has no bet")?;
+        if bet > self.money {
+            return Err(Box::new(BlJaError::NotEnoughMoney));
+        }
+        rules.check_bet(bet)
     }
 
-    /// Helper function to check if the player has enough money to split or double.
-    fn can_split_or_double(&self, hand: &Hand) -> bool {
+    /// Takes a guaranteed 1:1 payout on a natural against the dealer's ace, instead of insurance.
+    pub fn take_even_money(&mut self) -> Res<Rational64> {
+        let payout = self
+            .hands
+            .get_mut(0)
+            .ok_or("Player hand not initialized")?
+            .even_money()?;
+        self.collect(payout);
+        Ok(payout)
+    }
+
+    /// Surrenders the hand, and collects the refunded half of the bet.
+    pub fn surrender(&mut self, hand_num: usize) -> Res<Rational64> {
+        let refund = self
+            .hands
+            .get_mut(hand_num)
+            .ok_or("Player hand not initialized")?
+            .surrender()?;
+        self.collect(refund);
+        Ok(refund)
+    }
+
+    /// Helper function to check if the player has enough money to split or double, within the table's limits.
+    fn can_split_or_double(&self, hand: &Hand, rules: &TableRules) -> bool {
         let bet = hand.bet().unwrap();
-        bet <= self.money
+        bet <= self.money && rules.check_bet(bet).is_ok()
     }
 
     /// Get the actions available for the applicable hand.  This makes sure that the player has enough money
-    /// to support splitting or doubling.
-    pub fn actions(&self, hand: usize) -> HashSet<Action> {
+    /// to support splitting or doubling, and has not reached the table's limit of split hands.
+    pub fn actions(&self, hand: usize, rules: &TableRules) -> HashSet<Action> {
         let thehand = &self.hands[hand];
-        let mut actions = thehand.actions();
-        if !self.can_split_or_double(thehand) {
+        let mut actions = thehand.actions(rules);
+        if !self.can_split_or_double(thehand, rules) {
             actions.remove(&Action::Double);
             actions.remove(&Action::Split);
         }
+        if self.hands.len() >= rules.max_hands {
+            actions.remove(&Action::Split);
+        }
         actions
     }
 
@@ -155,9 +225,7 @@ impl Player {
 
     #[inline]
     pub fn display_money(&self) -> String {
-        // Include rounding to nearest hundreds place.
-        let float = self.money.to_f64().unwrap_or(0.0);
-        format!("{:.2}", float)
+        format_money(self.money)
     }
 
     #[inline]
@@ -175,9 +243,14 @@ impl Player {
         self.hands[hand].score()
     }
 
-    #[inline]
-    pub fn double(&mut self, bet: Rational64) {
+    /// Takes the additional bet for doubling down, which must be within the table's limits.
+    pub fn double(&mut self, bet: Rational64, rules: &TableRules) -> Res<()> {
+        if bet > self.money {
+            return Err(Box::new(BlJaError::NotEnoughMoney));
+        }
+        rules.check_bet(bet)?;
         self.money -= bet;
+        Ok(())
     }
 
     #[inline]
@@ -186,22 +259,60 @@ impl Player {
         self.hands.insert(index, newhand);
     }
 
-    /// Clears the players hand, sets the player to Out status if does not have any playable amount of money.
-    pub fn reset_after_round(&mut self, hand: usize) {
+    /// Clears the players hand, sets the player to Out status if they cannot cover the table minimum.
+    /// Returns the cards picked up from the hands, which go to the discard tray.
+    pub fn reset_after_round(&mut self, hand: usize, rules: &TableRules) -> Vec<Visible<Card>> {
         if self.hands.len() - 1 > hand {
-            return; // Do not reset, still have hands to check
+            return vec![]; // Do not reset, still have hands to check
         }
-        self.hands = vec![Hand::new()];
+        let hands = std::mem::replace(&mut self.hands, vec![Hand::new()]);
         self.insurance = Rational64::zero();
-        if self.money() < Rational64::one() {
+        if self.money() < rules.min_bet {
             self.status = Status::Out;
         }
+        hands
+            .iter()
+            .flat_map(|hand| hand.card_iter().copied())
+            .collect()
     }
 }
 
+/// Shows an amount of money rounded to the nearest hundredth, such as "2.50".
+pub fn format_money(money: Rational64) -> String {
+    format!("{:.2}", money.to_f64().unwrap_or(0.0))
+}
+
+/// Parses an exact amount of money, such as "250" or "12.75".  No rounding is done, so "0.1" is 1/10.
+pub fn parse_money(s: &str) -> Result<Rational64, BlJaError> {
+    let invalid = || BlJaError::InvalidMoney(s.to_string());
+    let trimmed = s.trim();
+    let (whole, fraction) = match trimmed.find('.') {
+        Some(point) => (&trimmed[..point], &trimmed[point + 1..]),
+        None => (trimmed, ""),
+    };
+    let digits = |part: &str| part.chars().all(|c| c.is_ascii_digit());
+    if (whole.is_empty() && fraction.is_empty()) || !digits(whole) || !digits(fraction) {
+        return Err(invalid());
+    }
+
+    let mut numer: i64 = 0;
+    let mut denom: i64 = 1;
+    for c in whole.chars().chain(fraction.chars()) {
+        numer = numer
+            .checked_mul(10)
+            .and_then(|n| n.checked_add(i64::from(c as u8 - b'0')))
+            .ok_or_else(invalid)?;
+    }
+    for _ in fraction.chars() {
+        denom = denom.checked_mul(10).ok_or_else(invalid)?;
+    }
+    Ok(Rational64::new(numer, denom))
+}
+
 #[cfg(test)]
 mod playertests {
     use super::*;
+    use crate::card::{Denomination, Suit};
     use num::ToPrimitive;
     use std::any::Any;
 
@@ -212,12 +323,14 @@ mod playertests {
     #[test]
     fn placebet() -> Res<()> {
         let mut aplayer = player();
-        aplayer.place_bet(200.into())?;
+        aplayer.place_bet(200.into(), &TableRules::default())?;
         assert_eq!(aplayer.money, 300.into());
         assert_eq!(aplayer.hands[0].bet().unwrap(), 200.into());
 
         let mut aplayer = player();
-        assert!(aplayer.place_bet(600.into()).is_err());
+        assert!(aplayer
+            .place_bet(600.into(), &TableRules::default())
+            .is_err());
         Ok(())
     }
 
@@ -225,25 +338,25 @@ mod playertests {
     fn insurance() -> Res<()> {
         // Max
         let mut aplayer = player();
-        aplayer.place_bet(200.into())?;
+        aplayer.place_bet(200.into(), &TableRules::default())?;
         aplayer.set_insurance(100.into())?;
         assert_eq!(aplayer.insurance(), 100.into());
 
         // One
         let mut aplayer = player();
-        aplayer.place_bet(200.into())?;
+        aplayer.place_bet(200.into(), &TableRules::default())?;
         aplayer.set_insurance(1.into())?;
         assert_eq!(aplayer.insurance(), 1.into());
 
         // Zero
         let mut aplayer = player();
-        aplayer.place_bet(200.into())?;
+        aplayer.place_bet(200.into(), &TableRules::default())?;
         aplayer.set_insurance(0.into())?;
         assert_eq!(aplayer.insurance(), 0.into());
 
         // Errors - Excessive insurance bet
         let mut aplayer = player();
-        aplayer.place_bet(200.into())?;
+        aplayer.place_bet(200.into(), &TableRules::default())?;
         let res = aplayer.set_insurance(Rational64::from((10001, 100))); // $100.01
         match res {
             Err(e) if e.is::<BlJaError>() => assert!(true),
@@ -255,7 +368,7 @@ mod playertests {
 
         // Errors - Not enough money
         let mut aplayer = player();
-        aplayer.place_bet(400.into())?;
+        aplayer.place_bet(400.into(), &TableRules::default())?;
         let res = aplayer.set_insurance(200.into());
         match res {
             Err(e) if e.is::<BlJaError>() => assert!(true),
@@ -274,4 +387,148 @@ mod playertests {
 
         Ok(())
     }
+
+    #[test]
+    fn max_hands() -> Res<()> {
+        let eight = || Visible::FacedUp(Card::new(Denomination::Numerical(8), Suit::Clubs));
+
+        let mut aplayer = player();
+        aplayer.place_bet(10.into(), &TableRules::default())?;
+        aplayer.hands[0].insert(eight());
+        aplayer.hands[0].insert(eight());
+        aplayer.split_hand(0, eight(), eight(), &TableRules::default())?;
+        assert_eq!(aplayer.num_hands(), 2);
+
+        let mut rules = TableRules {
+            max_hands: 3,
+            ..TableRules::default()
+        };
+        assert!(aplayer.actions(0, &rules).contains(&Action::Split));
+
+        rules.max_hands = 2;
+        let actions = aplayer.actions(0, &rules);
+        assert!(!actions.contains(&Action::Split));
+        assert!(actions.contains(&Action::Hit));
+
+        Ok(())
+    }
+
+    #[test]
+    fn bet_limits() -> Res<()> {
+        let eight = || Visible::FacedUp(Card::new(Denomination::Numerical(8), Suit::Clubs));
+        let rules = TableRules {
+            min_bet: 10.into(),
+            max_bet: Some(100.into()),
+            ..TableRules::default()
+        };
+        let limit = BlJaError::BetLimit {
+            min: 10.into(),
+            max: Some(100.into()),
+        };
+
+        let mut aplayer = player();
+        for bet in [0, 9, 101].iter() {
+            let err = aplayer.place_bet((*bet).into(), &rules).unwrap_err();
+            assert_eq!(err.downcast_ref::<BlJaError>(), Some(&limit));
+        }
+        aplayer.place_bet(10.into(), &rules)?;
+        assert_eq!(aplayer.money(), 490.into());
+
+        // The extra bet from splitting or doubling is held to the limits too
+        let err = aplayer.double(5.into(), &rules).unwrap_err();
+        assert_eq!(err.downcast_ref::<BlJaError>(), Some(&limit));
+        aplayer.hands[0].insert(eight());
+        aplayer.hands[0].insert(eight());
+        let higher = TableRules {
+            min_bet: 20.into(),
+            ..rules.clone()
+        };
+        assert!(!aplayer.actions(0, &higher).contains(&Action::Split));
+        assert!(!aplayer.actions(0, &higher).contains(&Action::Double));
+        assert!(aplayer.split_hand(0, eight(), eight(), &higher).is_err());
+        assert_eq!(aplayer.num_hands(), 1);
+        aplayer.split_hand(0, eight(), eight(), &rules)?;
+        assert_eq!(aplayer.money(), 480.into());
+
+        // Out once the bankroll is below the minimum.  The cards of both hands are picked up
+        assert!(aplayer.reset_after_round(0, &rules).is_empty());
+        assert_eq!(aplayer.reset_after_round(1, &rules).len(), 4);
+        assert_eq!(aplayer.status(), Status::Playing);
+        aplayer.money = 9.into();
+        assert!(aplayer.reset_after_round(0, &rules).is_empty());
+        assert_eq!(aplayer.status(), Status::Out);
+
+        // Limits and bets can be decimals, like the bankroll
+        let decimal = TableRules {
+            min_bet: parse_money("2.50")?,
+            ..TableRules::default()
+        };
+        let mut aplayer = player();
+        assert!(aplayer.place_bet(parse_money("2.49")?, &decimal).is_err());
+        aplayer.place_bet(parse_money("2.50")?, &decimal)?;
+        assert_eq!(aplayer.display_money(), "497.50");
+        let limit = BlJaError::BetLimit {
+            min: parse_money("2.5")?,
+            max: None,
+        };
+        assert_eq!(limit.to_string(), "Bet is below the table minimum of 2.50");
+
+        Ok(())
+    }
+
+    #[test]
+    fn even_money() -> Res<()> {
+        let ace = Visible::FacedUp(Card::new(Denomination::Ace, Suit::Spades));
+        let king = Visible::FacedUp(Card::new(Denomination::King, Suit::Spades));
+        let nine = Visible::FacedUp(Card::new(Denomination::Numerical(9), Suit::Spades));
+
+        let mut aplayer = player();
+        aplayer.place_bet(100.into(), &TableRules::default())?;
+        aplayer.hands[0].insert(ace);
+        aplayer.hands[0].insert(king);
+        assert_eq!(aplayer.take_even_money()?, 200.into());
+        assert_eq!(aplayer.money(), 600.into());
+        assert_eq!(aplayer.hands[0].hand_type(), HandType::EvenMoney);
+
+        // Cannot be paid twice, or insure the hand afterwards
+        assert!(aplayer.take_even_money().is_err());
+        assert!(aplayer.set_insurance(50.into()).is_err());
+        assert_eq!(aplayer.money(), 600.into());
+
+        // Only paid on naturals
+        let mut aplayer = player();
+        aplayer.place_bet(100.into(), &TableRules::default())?;
+        aplayer.hands[0].insert(king);
+        aplayer.hands[0].insert(nine);
+        assert!(aplayer.take_even_money().is_err());
+        aplayer.set_insurance(50.into())?;
+        assert_eq!(aplayer.money(), 350.into());
+
+        Ok(())
+    }
+
+    #[test]
+    fn seats() {
+        let names = vec!["Ann".to_string(), "Bob".to_string()];
+        let seats = Player::seats(3, &names, Rational64::new(501, 2));
+        let names: Vec<_> = seats.iter().map(|p| p.name().as_str()).collect();
+        assert_eq!(names, vec!["Ann", "Bob", "Player 3"]);
+        assert!(seats.iter().all(|p| p.money() == Rational64::new(501, 2)));
+    }
+
+    #[test]
+    fn money() -> Res<()> {
+        assert_eq!(parse_money("250")?, 250.into());
+        assert_eq!(parse_money(" 12.75 ")?, Rational64::new(51, 4));
+        assert_eq!(parse_money("0.1")?, Rational64::new(1, 10));
+        assert_eq!(parse_money(".5")?, Rational64::new(1, 2));
+        assert_eq!(parse_money("7.")?, 7.into());
+        for bad in ["", ".", "-5", "1.2.3", "12a", "1e3", "99999999999999999999"].iter() {
+            assert_eq!(
+                parse_money(bad).unwrap_err(),
+                BlJaError::InvalidMoney(bad.to_string())
+            );
+        }
+        Ok(())
+    }
 }
@@ -0,0 +1,378 @@
+//! Player profiles saved on disk, so a bankroll and lifetime stats carry across sessions.  Each profile is a
+//! JSON file named after the player, kept in the XDG data directory.
+
+use crate::engine::{Engine, Outcome, Settlement};
+use crate::errors::BlJaError;
+use crate::player::Player;
+use crate::table::Table;
+use crate::Res;
+use num::{Rational64, Zero};
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::ffi::{OsStr, OsString};
+use std::fs;
+use std::path::PathBuf;
+
+/// Longest name allowed for a profile.
+const MAX_NAME_LEN: usize = 24;
+
+/// Counts of how every hand played with the profile turned out.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Stats {
+    pub hands: u64,
+    pub wins: u64,
+    pub losses: u64,
+    pub pushes: u64,
+    pub blackjacks: u64,
+    pub surrenders: u64,
+    /// Winnings across every session.  Negative when down.
+    pub net: Rational64,
+}
+
+impl Default for Stats {
+    fn default() -> Stats {
+        Stats {
+            hands: 0,
+            wins: 0,
+            losses: 0,
+            pushes: 0,
+            blackjacks: 0,
+            surrenders: 0,
+            net: Rational64::zero(),
+        }
+    }
+}
+
+/// Settings the player can change by editing their profile.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Preferences {
+    /// Filled in as the bet when the player's turn to bet comes up.
+    pub default_bet: Option<Rational64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Profile {
+    name: String,
+    bankroll: Rational64,
+    #[serde(default)]
+    stats: Stats,
+    #[serde(default)]
+    preferences: Preferences,
+}
+
+impl Profile {
+    /// A new profile starting with the table's bankroll.
+    pub fn new(name: String, bankroll: Rational64) -> Profile {
+        Profile {
+            name,
+            bankroll,
+            stats: Stats::default(),
+            preferences: Preferences::default(),
+        }
+    }
+
+    /// A player to take a seat with the profile's bankroll.
+    pub fn player(&self) -> Player {
+        Player::with_money(self.name.clone(), self.bankroll)
+    }
+
+    /// Adds a settled hand to the stats.
+    pub fn record(&mut self, settlement: &Settlement) {
+        self.stats.hands += 1;
+        match settlement.outcome {
+            Outcome::Blackjack | Outcome::EvenMoney => {
+                self.stats.wins += 1;
+                self.stats.blackjacks += 1;
+            }
+            Outcome::Win => self.stats.wins += 1,
+            Outcome::Push => self.stats.pushes += 1,
+            Outcome::Lose | Outcome::Bust => self.stats.losses += 1,
+            Outcome::Surrender => self.stats.surrenders += 1,
+        }
+    }
+
+    /// Sets the bankroll to the money the player now has, adding the difference to the net winnings.
+    pub fn update_bankroll(&mut self, money: Rational64) {
+        self.stats.net += money - self.bankroll;
+        self.bankroll = money;
+    }
+
+    #[inline]
+    pub fn name(&self) -> &String {
+        &self.name
+    }
+
+    #[inline]
+    pub fn stats(&self) -> &Stats {
+        &self.stats
+    }
+
+    #[inline]
+    pub fn preferences(&self) -> &Preferences {
+        &self.preferences
+    }
+}
+
+/// The directory the profiles are kept in.
+#[derive(Debug, Clone)]
+pub struct ProfileStore {
+    dir: PathBuf,
+}
+
+impl ProfileStore {
+    pub fn new(dir: PathBuf) -> ProfileStore {
+        ProfileStore { dir }
+    }
+
+    /// Profiles in `$XDG_DATA_HOME/blackjack/profiles`, which is `~/.local/share/blackjack/profiles` if not set.
+    pub fn from_env() -> Res<ProfileStore> {
+        data_dir(env::var_os("XDG_DATA_HOME"), env::var_os("HOME"))
+            .map(|dir| ProfileStore::new(dir.join("blackjack").join("profiles")))
+            .ok_or_else(|| {
+                BlJaError::InvalidProfile("No data directory.  Set XDG_DATA_HOME or HOME".into())
+                    .into()
+            })
+    }
+
+    /// Names of the saved profiles, in order.
+    pub fn names(&self) -> Res<Vec<String>> {
+        if !self.dir.is_dir() {
+            return Ok(vec![]);
+        }
+        let mut names = vec![];
+        for entry in fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            if path.extension() == Some(OsStr::new("json")) {
+                if let Some(name) = path.file_stem().and_then(|stem| stem.to_str()) {
+                    names.push(name.to_string());
+                }
+            }
+        }
+        names.sort();
+        Ok(names)
+    }
+
+    /// Reads the profile, or starts a new one with the bankroll if the name has not been used.
+    pub fn load_or_create(&self, name: &str, bankroll: Rational64) -> Res<Profile> {
+        let path = self.path(name)?;
+        if !path.exists() {
+            return Ok(Profile::new(name.to_string(), bankroll));
+        }
+        let json = fs::read_to_string(&path)?;
+        let profile: Profile = serde_json::from_str(&json)
+            .map_err(|e| BlJaError::InvalidProfile(format!("{}: {}", path.display(), e)))?;
+        Ok(profile)
+    }
+
+    pub fn save(&self, profile: &Profile) -> Res<()> {
+        let path = self.path(&profile.name)?;
+        fs::create_dir_all(&self.dir)?;
+        fs::write(path, serde_json::to_string_pretty(profile)?)?;
+        Ok(())
+    }
+
+    /// The profile's file.  Names are used as file names, so they cannot hold path separators.
+    fn path(&self, name: &str) -> Res<PathBuf> {
+        let valid = !name.trim().is_empty()
+            && name.len() <= MAX_NAME_LEN
+            && !name.starts_with('.')
+            && !name.contains(|c: char| c == '/' || c == '\\' || c.is_control());
+        if !valid {
+            return Err(Box::new(BlJaError::InvalidProfile(format!(
+                "'{}' is not a valid name",
+                name
+            ))));
+        }
+        Ok(self.dir.join(format!("{}.json", name)))
+    }
+}
+
+/// XDG data directory, falling back to `~/.local/share`.  Relative paths are ignored as the spec requires.
+fn data_dir(xdg_data_home: Option<OsString>, home: Option<OsString>) -> Option<PathBuf> {
+    xdg_data_home
+        .map(PathBuf::from)
+        .filter(|dir| dir.is_absolute())
+        .or_else(|| {
+            home.map(PathBuf::from)
+                .filter(|dir| dir.is_absolute())
+                .map(|home| home.join(".local").join("share"))
+        })
+}
+
+/// The profiles seated at the table.  Stats are recorded as hands settle, and bankrolls are saved when
+/// rounds end, so leaving in the middle of a round does not lose or keep the bets on the table.
+#[derive(Debug)]
+pub struct Profiles {
+    store: ProfileStore,
+    seats: Vec<Option<Profile>>,
+    // What new profiles start with
+    bankroll: Rational64,
+}
+
+impl Profiles {
+    pub fn new(store: ProfileStore, seats: usize, bankroll: Rational64) -> Profiles {
+        Profiles {
+            store,
+            seats: vec![None; seats],
+            bankroll,
+        }
+    }
+
+    #[inline]
+    pub fn store(&self) -> &ProfileStore {
+        &self.store
+    }
+
+    /// The profile at the seat, if the player there has one.
+    pub fn get(&self, seat: usize) -> Option<&Profile> {
+        self.seats.get(seat).and_then(Option::as_ref)
+    }
+
+    /// Seats the named profile, creating it if new.  A profile can only be at one seat.
+    pub fn sit(&mut self, seat: usize, name: &str, engine: &mut Engine) -> Res<()> {
+        let name = name.trim();
+        if self
+            .seats
+            .iter()
+            .enumerate()
+            .any(|(i, p)| i != seat && matches!(p, Some(p) if p.name() == name))
+        {
+            return Err(Box::new(BlJaError::InvalidProfile(format!(
+                "{} is already seated",
+                name
+            ))));
+        }
+        let profile = self.store.load_or_create(name, self.bankroll)?;
+        engine.seat(seat, profile.player())?;
+        self.store.save(&profile)?;
+        self.seats[seat] = Some(profile);
+        Ok(())
+    }
+
+    /// Adds the hand to the stats of the seat's profile.
+    pub fn record(&mut self, settlement: &Settlement) {
+        if let Some(Some(profile)) = self.seats.get_mut(settlement.player) {
+            profile.record(settlement);
+        }
+    }
+
+    /// Saves each seated profile with the money its player now has.
+    pub fn save(&mut self, table: &Table) -> Res<()> {
+        for (seat, profile) in self.seats.iter_mut().enumerate() {
+            if let (Some(profile), Some(player)) = (profile, table.player(seat)) {
+                profile.update_bankroll(player.borrow().money());
+                self.store.save(profile)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod profiletests {
+    use super::*;
+    use crate::constants::STARTING_MONEY;
+    use crate::engine::{Command, Event, Phase};
+    use crate::hand::Action;
+    use crate::rules::TableRules;
+    use std::path::Path;
+
+    fn temp_store(name: &str) -> ProfileStore {
+        let dir = env::temp_dir().join(format!("blackjack-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        ProfileStore::new(dir)
+    }
+
+    #[test]
+    fn store() -> Res<()> {
+        let store = temp_store("store");
+        assert!(store.names()?.is_empty());
+
+        let start = Rational64::from_integer(STARTING_MONEY);
+        let mut profile = store.load_or_create("Ann", start)?;
+        assert_eq!(
+            profile.player().money(),
+            Rational64::from_integer(STARTING_MONEY)
+        );
+        profile.update_bankroll(Rational64::new(1001, 2));
+        store.save(&profile)?;
+        store.save(&Profile::new("Bob".to_string(), start))?;
+
+        assert_eq!(store.names()?, vec!["Ann".to_string(), "Bob".to_string()]);
+        let loaded = store.load_or_create("Ann", 1.into())?;
+        assert_eq!(loaded, profile);
+        assert_eq!(loaded.stats().net, Rational64::new(1, 2));
+
+        for name in ["", "  ", "../Ann", ".hidden", "a\\b"].iter() {
+            assert!(store.load_or_create(name, start).is_err());
+        }
+        fs::remove_dir_all(&store.dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn xdg_data_dir() {
+        assert_eq!(
+            data_dir(Some("/data".into()), Some("/home/ann".into())),
+            Some(PathBuf::from("/data"))
+        );
+        assert_eq!(
+            data_dir(Some("data".into()), Some("/home/ann".into())),
+            Some(Path::new("/home/ann").join(".local").join("share"))
+        );
+        assert_eq!(data_dir(None, None), None);
+    }
+
+    #[test]
+    fn sessions() -> Res<()> {
+        let store = temp_store("sessions");
+        let mut profile = Profile::new("Ann".to_string(), STARTING_MONEY.into());
+        profile.update_bankroll(1000.into());
+        store.save(&profile)?;
+
+        let mut engine = Engine::new(Table::new(
+            Player::seats(2, &[], 500.into()),
+            2,
+            TableRules::default(),
+        )?);
+        engine.start();
+        let mut profiles = Profiles::new(store.clone(), 2, 200.into());
+        profiles.sit(0, "Ann", &mut engine)?;
+        assert!(profiles.sit(1, "Ann", &mut engine).is_err());
+        profiles.sit(1, "Bob", &mut engine)?;
+        assert_eq!(
+            engine.table().player(0).unwrap().borrow().money(),
+            1000.into()
+        );
+
+        // Play the round out, standing on everything
+        engine.handle(Command::Bet(100.into()))?;
+        engine.handle(Command::Bet(100.into()))?;
+        let mut settled = 0;
+        loop {
+            let command = match engine.phase() {
+                Phase::Betting { .. } | Phase::GameOver => break,
+                Phase::Playing { .. } => Command::Act(Action::Stand),
+                Phase::Insurance { .. } => Command::Insure(0.into()),
+                _ => Command::Continue,
+            };
+            for event in engine.handle(command)? {
+                if let Event::Settled(settlement) = event {
+                    profiles.record(&settlement);
+                    settled += 1;
+                }
+            }
+        }
+        profiles.save(engine.table())?;
+
+        let ann = store.load_or_create("Ann", 1.into())?;
+        let money = engine.table().player(0).unwrap().borrow().money();
+        assert_eq!(ann.player().money(), money);
+        assert_eq!(ann.stats().net, money - STARTING_MONEY);
+        let bob = store.load_or_create("Bob", 1.into())?;
+        assert_eq!(ann.stats().hands + bob.stats().hands, settled);
+        assert_eq!(bob.stats().net, bob.player().money() - 200);
+        fs::remove_dir_all(&store.dir)?;
+        Ok(())
+    }
+}
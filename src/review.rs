@@ -0,0 +1,262 @@
+//! Mistake tracking.  Each play is checked against basic strategy, and plays that differ are tallied with
+//! the expected value they give up, so a player can see which mistakes they make most and which cost the
+//! most.  A session's `Review` keeps a tally for every player by name, so it carries over restarts.
+
+use crate::card::{BlackJackScore, Card};
+use crate::hand::{Action, Hand, HandType};
+use crate::rules::TableRules;
+use crate::strategy;
+use std::collections::HashSet;
+use std::fmt::Write;
+
+/// How many of the most frequent and most costly mistakes are reported for each player.
+pub const REPORTED: usize = 3;
+
+/// Every time a player made the same play against basic strategy in the same situation.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mistake {
+    /// The hand, such as "soft 18" or "pair 8"
+    pub hand: String,
+    /// The dealer's up card, such as "6" or "A"
+    pub up: String,
+    pub chosen: Action,
+    pub advised: Action,
+    pub times: usize,
+    /// Expected value given up over all the times, in bets
+    pub cost: f64,
+}
+
+/// The plays one player has made.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Plays {
+    decisions: usize,
+    mistakes: Vec<Mistake>,
+}
+
+impl Plays {
+    /// Checks the chosen action against basic strategy.  If it differs, it is tallied and its cost
+    /// returned.
+    pub fn check(
+        &mut self,
+        hand: &Hand,
+        up_card: &Card,
+        rules: &TableRules,
+        actions: &HashSet<Action>,
+        chosen: Action,
+    ) -> Option<f64> {
+        let advised = strategy::advise(hand, up_card, rules, actions)?;
+        self.decisions += 1;
+        if chosen == advised {
+            return None;
+        }
+
+        let cost = strategy::cost(hand, up_card, rules, chosen, advised);
+        let (hand, up) = (describe(hand, actions), value(up_card.score()));
+        match self
+            .mistakes
+            .iter_mut()
+            .find(|m| m.hand == hand && m.up == up && m.chosen == chosen && m.advised == advised)
+        {
+            Some(mistake) => {
+                mistake.times += 1;
+                mistake.cost += cost;
+            }
+            None => self.mistakes.push(Mistake {
+                hand,
+                up,
+                chosen,
+                advised,
+                times: 1,
+                cost,
+            }),
+        }
+        Some(cost)
+    }
+
+    #[inline]
+    pub fn decisions(&self) -> usize {
+        self.decisions
+    }
+
+    /// The number of plays that differed from basic strategy.
+    pub fn mistakes(&self) -> usize {
+        self.mistakes.iter().map(|m| m.times).sum()
+    }
+
+    /// Expected value given up by all the mistakes, in bets.
+    pub fn cost(&self) -> f64 {
+        self.mistakes.iter().map(|m| m.cost).sum()
+    }
+
+    /// The mistakes made most often, the costliest first among equals.
+    pub fn most_frequent(&self, n: usize) -> Vec<&Mistake> {
+        let mut mistakes: Vec<&Mistake> = self.mistakes.iter().collect();
+        mistakes.sort_by(|a, b| b.times.cmp(&a.times).then(b.cost.total_cmp(&a.cost)));
+        mistakes.truncate(n);
+        mistakes
+    }
+
+    /// The mistakes that have given up the most expected value.
+    pub fn most_costly(&self, n: usize) -> Vec<&Mistake> {
+        let mut mistakes: Vec<&Mistake> = self.mistakes.iter().collect();
+        mistakes.sort_by(|a, b| b.cost.total_cmp(&a.cost));
+        mistakes.truncate(n);
+        mistakes
+    }
+}
+
+/// The plays of everyone who has played during a session.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Review {
+    players: Vec<(String, Plays)>,
+}
+
+impl Review {
+    /// The plays of the named player, starting a tally if they have not played yet.
+    pub fn player(&mut self, name: &str) -> &mut Plays {
+        let index = match self.players.iter().position(|(n, _)| n == name) {
+            Some(index) => index,
+            None => {
+                self.players.push((name.to_string(), Plays::default()));
+                self.players.len() - 1
+            }
+        };
+        &mut self.players[index].1
+    }
+
+    /// Whether no one has made a play yet.
+    pub fn is_empty(&self) -> bool {
+        self.players.iter().all(|(_, plays)| plays.decisions() == 0)
+    }
+
+    /// A report of each player's most frequent and most costly mistakes.
+    pub fn report(&self) -> String {
+        let mut report = String::new();
+        for (name, plays) in self.players.iter().filter(|(_, p)| p.decisions() > 0) {
+            if plays.mistakes.is_empty() {
+                writeln!(
+                    report,
+                    "{}: no mistakes in {} plays",
+                    name,
+                    plays.decisions()
+                )
+                .ok();
+                continue;
+            }
+            writeln!(
+                report,
+                "{}: {} mistakes in {} plays, costing {:.2} bets",
+                name,
+                plays.mistakes(),
+                plays.decisions(),
+                plays.cost()
+            )
+            .ok();
+            writeln!(report, "  Most frequent:").ok();
+            for mistake in plays.most_frequent(REPORTED) {
+                writeln!(report, "    {}", show(mistake)).ok();
+            }
+            writeln!(report, "  Most costly:").ok();
+            for mistake in plays.most_costly(REPORTED) {
+                writeln!(report, "    {}", show(mistake)).ok();
+            }
+        }
+        if report.is_empty() {
+            report.push_str("No plays yet.");
+        }
+        report
+    }
+}
+
+fn show(mistake: &Mistake) -> String {
+    format!(
+        "{} against {}: {:?} instead of {:?}, {} times, costing {:.2} bets",
+        mistake.hand, mistake.up, mistake.chosen, mistake.advised, mistake.times, mistake.cost
+    )
+}
+
+/// The hand as basic strategy sees it, such as "hard 12", "soft 18" or "pair A".
+fn describe(hand: &Hand, actions: &HashSet<Action>) -> String {
+    use HandType::*;
+    match hand.card_iter().next() {
+        Some(card) if actions.contains(&Action::Split) => format!("pair {}", value(card.score())),
+        _ => match hand.hand_type() {
+            Soft | SplitSoft | SplitAces | DoubledSoft => format!("soft {}", hand.score()),
+            _ => format!("hard {}", hand.score()),
+        },
+    }
+}
+
+/// A card's value as shown in a strategy chart.
+fn value(score: usize) -> String {
+    match score {
+        11 => "A".to_string(),
+        _ => score.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod reviewtests {
+    use super::*;
+    use crate::card::Denomination::{self, *};
+    use crate::card::Suit;
+    use crate::Res;
+
+    fn play(plays: &mut Plays, hand: &str, up: Denomination, chosen: Action) -> Res<Option<f64>> {
+        let rules = TableRules::default();
+        let hand: Hand = hand.parse()?;
+        let actions = hand.actions(&rules);
+        Ok(plays.check(
+            &hand,
+            &Card::new(up, Suit::Hearts),
+            &rules,
+            &actions,
+            chosen,
+        ))
+    }
+
+    #[test]
+    fn mistakes() -> Res<()> {
+        let mut review = Review::default();
+        let plays = review.player("Alice");
+        assert_eq!(play(plays, "hard 16", Numerical(6), Action::Stand)?, None);
+        for _ in 0..3 {
+            assert!(play(plays, "soft 18", Numerical(4), Action::Stand)?.unwrap() > 0.);
+        }
+        play(plays, "hard 19", Numerical(6), Action::Hit)?;
+        play(plays, "pair 8", Ace, Action::Hit)?;
+
+        assert_eq!(plays.decisions(), 6);
+        assert_eq!(plays.mistakes(), 5);
+
+        let frequent = plays.most_frequent(1);
+        assert_eq!(frequent[0].hand, "soft 18");
+        assert_eq!(frequent[0].up, "4");
+        assert_eq!(frequent[0].times, 3);
+        assert_eq!(
+            (frequent[0].chosen, frequent[0].advised),
+            (Action::Stand, Action::Double)
+        );
+
+        let costly = plays.most_costly(REPORTED);
+        assert_eq!(costly.len(), 3);
+        assert_eq!(costly[0].hand, "hard 19");
+        // Costs add up over the times a mistake is made
+        assert_eq!(costly[1].hand, "soft 18");
+        assert_eq!(costly[2].hand, "pair 8");
+        assert_eq!(costly[2].up, "A");
+        assert!((plays.cost() - costly.iter().map(|m| m.cost).sum::<f64>()).abs() < 1e-9);
+
+        // Tallies are kept by name
+        play(review.player("Bob"), "hard 12", Numerical(4), Action::Stand)?;
+        assert_eq!(review.player("Alice").decisions(), 6);
+        let report = review.report();
+        assert!(report.contains("Alice: 5 mistakes in 6 plays"));
+        assert!(report.contains("soft 18 against 4: Stand instead of Double, 3 times"));
+        assert!(report.contains("Bob: no mistakes in 1 plays"));
+        assert!(!review.is_empty());
+        assert!(Review::default().is_empty());
+        assert_eq!(Review::default().report(), "No plays yet.");
+        Ok(())
+    }
+}
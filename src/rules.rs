@@ -0,0 +1,383 @@
+//! The rules that vary from table to table.  The defaults are the Bicycle casino rules the game was
+//! originally written for.
+
+use crate::errors::BlJaError;
+use crate::hand::{Hand, HandType};
+use crate::Res;
+use num::Rational64;
+use rand::Rng;
+use std::str::FromStr;
+
+/// Which two card hands can be doubled down on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DoubleRule {
+    AnyTwo,
+    /// Only hard 9, 10, or 11
+    NineToEleven,
+    /// Only hard 10 or 11
+    TenToEleven,
+}
+
+/// When a player may give up their first two cards for half of the bet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SurrenderRule {
+    NotAllowed,
+    /// Only after the dealer has checked for a natural
+    Late,
+    /// Before the dealer checks for a natural, as well as after
+    Early,
+}
+
+/// How the dealer's second card is dealt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DealingMode {
+    /// A face down hole card is dealt with the players' cards, and the dealer peeks for a natural.
+    HoleCard,
+    /// European no hole card.  The dealer's second card is drawn after all players finish, so a dealer
+    /// natural takes doubled and split bets too, unless only the original bet is lost.
+    NoHoleCard { original_bets_only: bool },
+}
+
+/// How deep into the shoe the cut card is placed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Penetration {
+    /// Percentage of the shoe dealt before the cut card
+    Percent(u8),
+    /// Cards dealt before the cut card
+    Cards(usize),
+}
+
+/// Where the cut card goes after each shuffle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CutCard {
+    pub penetration: Penetration,
+    /// The cut card is moved up to this many cards either way, so it is not in the same place every shoe.
+    pub jitter: usize,
+}
+
+/// The rules a table is played by.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TableRules {
+    /// Smallest bet, and the least a player needs to stay at the table.
+    pub min_bet: Rational64,
+    /// Largest bet, if the table has a limit.
+    pub max_bet: Option<Rational64>,
+    pub dealer_hits_soft_17: bool,
+    /// Winnings paid on a natural, per unit bet.
+    pub blackjack_payout: Rational64,
+    /// Winnings paid on insurance, per unit bet.
+    pub insurance_payout: Rational64,
+    pub double: DoubleRule,
+    pub double_after_split: bool,
+    /// Most hands a player can hold from splitting and resplitting.
+    pub max_hands: usize,
+    pub resplit_aces: bool,
+    /// Split aces normally only get one card each.
+    pub hit_split_aces: bool,
+    pub surrender: SurrenderRule,
+    pub dealing: DealingMode,
+    /// Without a cut card, a shoe is cut 60 to 75 cards from the back, and a single deck is reshuffled after
+    /// every round.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub cut_card: Option<CutCard>,
+    /// Cards burned from the top of each new shoe into the discard tray.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub burn: usize,
+    /// Burned cards are normally put in the discard tray face down, unseen by the players.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub burn_face_up: bool,
+}
+
+impl Default for TableRules {
+    fn default() -> Self {
+        TableRules {
+            min_bet: Rational64::from_integer(1),
+            max_bet: None,
+            dealer_hits_soft_17: false,
+            blackjack_payout: Rational64::new(3, 2),
+            insurance_payout: Rational64::from_integer(2),
+            double: DoubleRule::AnyTwo,
+            double_after_split: true,
+            max_hands: 4,
+            resplit_aces: false,
+            hit_split_aces: false,
+            surrender: SurrenderRule::NotAllowed,
+            dealing: DealingMode::HoleCard,
+            cut_card: None,
+            burn: 0,
+            burn_face_up: false,
+        }
+    }
+}
+
+impl TableRules {
+    /// Checks a wager against the table's limits.
+    pub fn check_bet(&self, bet: Rational64) -> Res<()> {
+        if bet < self.min_bet || matches!(self.max_bet, Some(max) if bet > max) {
+            return Err(Box::new(BlJaError::BetLimit {
+                min: self.min_bet,
+                max: self.max_bet,
+            }));
+        }
+        Ok(())
+    }
+
+    /// Checks the double down restrictions against a two card hand.
+    pub fn allows_double(&self, hand: &Hand) -> bool {
+        use HandType::*;
+        let (split, soft) = match hand.hand_type() {
+            Normal => (false, false),
+            Soft => (false, true),
+            Split => (true, false),
+            SplitSoft => (true, true),
+            _ => return false,
+        };
+        if split && !self.double_after_split {
+            return false;
+        }
+        match self.double {
+            DoubleRule::AnyTwo => true,
+            DoubleRule::NineToEleven => !soft && (9..=11).contains(&hand.score()),
+            DoubleRule::TenToEleven => !soft && (10..=11).contains(&hand.score()),
+        }
+    }
+
+    /// If the dealer must draw another card to the hand.
+    pub fn dealer_hits(&self, hand: &Hand) -> bool {
+        hand.score() < 17
+            || (self.dealer_hits_soft_17
+                && hand.score() == 17
+                && hand.hand_type() == HandType::Soft)
+    }
+}
+
+impl CutCard {
+    /// Cards left behind the cut card in a shoe of `cards`.  At least one card is dealt before it, and one is
+    /// left behind it.
+    pub fn cards_behind<R: Rng + ?Sized>(&self, cards: usize, rng: &mut R) -> usize {
+        let dealt = match self.penetration {
+            Penetration::Percent(percent) => cards * percent as usize / 100,
+            Penetration::Cards(dealt) => dealt,
+        };
+        let jitter = self.jitter as i64;
+        let moved = if jitter > 0 {
+            rng.gen_range(-jitter..=jitter)
+        } else {
+            0
+        };
+        let dealt = (dealt as i64 + moved).clamp(1, cards as i64 - 1);
+        cards - dealt as usize
+    }
+}
+
+/// Parses a penetration written as a percentage of the shoe, such as "75%", or as a number of cards.
+impl FromStr for Penetration {
+    type Err = BlJaError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let invalid = || BlJaError::InvalidPenetration(s.to_string());
+        match s.strip_suffix('%') {
+            Some(percent) => match percent.trim().parse() {
+                Ok(percent) if (1..=99).contains(&percent) => Ok(Penetration::Percent(percent)),
+                _ => Err(invalid()),
+            },
+            None => match s.parse() {
+                Ok(cards) if cards > 0 => Ok(Penetration::Cards(cards)),
+                _ => Err(invalid()),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod rulestests {
+    use super::*;
+    use crate::card::Denomination::*;
+    use crate::card::{Card, Denomination, Suit, Visible};
+    use crate::hand::Action;
+    use crate::Res;
+
+    fn hand(cards: &[Denomination]) -> Hand {
+        let mut hand = Hand::new();
+        for denom in cards {
+            hand.insert(Visible::FacedUp(Card::new(*denom, Suit::Clubs)));
+        }
+        hand
+    }
+
+    /// One of each score, as jacks, queens, and kings play the same as tens.
+    fn denominations() -> Vec<Denomination> {
+        let mut denoms = vec![Ace, King];
+        denoms.extend((2..=10).map(Numerical));
+        denoms
+    }
+
+    /// If the rule allows doubling a two card total, soft or hard.
+    fn expect_double(rule: DoubleRule, total: usize, soft: bool) -> bool {
+        match rule {
+            DoubleRule::AnyTwo => true,
+            DoubleRule::NineToEleven => !soft && (9..=11).contains(&total),
+            DoubleRule::TenToEleven => !soft && (10..=11).contains(&total),
+        }
+    }
+
+    const DOUBLE_RULES: [DoubleRule; 3] = [
+        DoubleRule::AnyTwo,
+        DoubleRule::NineToEleven,
+        DoubleRule::TenToEleven,
+    ];
+
+    #[test]
+    fn double_restrictions() {
+        for &double in DOUBLE_RULES.iter() {
+            let rules = TableRules {
+                double,
+                ..TableRules::default()
+            };
+            for first in denominations() {
+                for second in denominations() {
+                    let hand = hand(&[first, second]);
+                    let soft = first == Ace || second == Ace;
+                    let expected = match hand.hand_type() {
+                        HandType::Natural => false,
+                        _ => expect_double(double, hand.score(), soft),
+                    };
+                    assert_eq!(
+                        hand.actions(&rules).contains(&Action::Double),
+                        expected,
+                        "{:?} with {:?}, {:?}",
+                        double,
+                        first,
+                        second
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn double_after_split() -> Res<()> {
+        for &double in DOUBLE_RULES.iter() {
+            for &das in [true, false].iter() {
+                let rules = TableRules {
+                    double,
+                    double_after_split: das,
+                    ..TableRules::default()
+                };
+                for first in denominations() {
+                    for second in denominations() {
+                        let mut pair = hand(&[first, first]);
+                        pair.set_bet(10.into());
+                        let (split, _) = pair.split_hand()?;
+                        let split = split.insert(Visible::FacedUp(Card::new(second, Suit::Hearts)));
+                        let soft = first == Ace || second == Ace;
+
+                        // Split aces only get one card, so are never doubled
+                        let expected =
+                            das && first != Ace && expect_double(double, split.score(), soft);
+                        assert_eq!(
+                            split.actions(&rules).contains(&Action::Double),
+                            expected,
+                            "{:?} with {:?}, {:?}, DAS {}",
+                            double,
+                            first,
+                            second,
+                            das
+                        );
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn dealer_soft_17() {
+        let s17 = TableRules::default();
+        let h17 = TableRules {
+            dealer_hits_soft_17: true,
+            ..TableRules::default()
+        };
+
+        // Soft 17s only draw under H17
+        let soft17: [&[Denomination]; 3] = [
+            &[Ace, Numerical(6)],
+            &[Numerical(2), Ace, Numerical(4)],
+            &[Ace, Ace, Numerical(5)],
+        ];
+        for cards in soft17.iter() {
+            let hand = hand(cards);
+            assert_eq!(hand.score(), 17);
+            assert_eq!(hand.hand_type(), HandType::Soft);
+            assert!(!s17.dealer_hits(&hand));
+            assert!(h17.dealer_hits(&hand));
+        }
+
+        // Hard 17 and soft 18 stand under both
+        let stands: [&[Denomination]; 3] = [
+            &[King, Numerical(7)],
+            &[Ace, Numerical(6), King],
+            &[Ace, Numerical(7)],
+        ];
+        for cards in stands.iter() {
+            let hand = hand(cards);
+            assert!(!s17.dealer_hits(&hand));
+            assert!(!h17.dealer_hits(&hand));
+        }
+
+        // Hard and soft 16 draw under both
+        let hits: [&[Denomination]; 2] = [&[King, Numerical(6)], &[Ace, Numerical(5)]];
+        for cards in hits.iter() {
+            let hand = hand(cards);
+            assert!(s17.dealer_hits(&hand));
+            assert!(h17.dealer_hits(&hand));
+        }
+    }
+
+    #[test]
+    fn cut_card() -> Res<()> {
+        use rand::SeedableRng;
+        use rand_chacha::ChaCha12Rng;
+        use std::collections::HashSet;
+
+        let mut rng = ChaCha12Rng::seed_from_u64(4);
+        let cut =
+            |penetration: &str, jitter: usize, cards: usize, rng: &mut ChaCha12Rng| -> Res<usize> {
+                let cut_card = CutCard {
+                    penetration: penetration.parse()?,
+                    jitter,
+                };
+                Ok(cut_card.cards_behind(cards, rng))
+            };
+
+        assert_eq!(cut("75%", 0, 312, &mut rng)?, 78);
+        assert_eq!(cut("50%", 0, 52, &mut rng)?, 26);
+        assert_eq!(cut("40", 0, 52, &mut rng)?, 12);
+        // There is always a card on either side of the cut card
+        assert_eq!(cut("60", 0, 52, &mut rng)?, 1);
+        assert_eq!(cut("1%", 0, 52, &mut rng)?, 51);
+
+        let mut seen = HashSet::new();
+        for _ in 0..200 {
+            let behind = cut("75%", 5, 312, &mut rng)?;
+            assert!((73..=83).contains(&behind));
+            seen.insert(behind);
+        }
+        assert_eq!(seen.len(), 11);
+
+        for bad in ["0%", "100%", "75.5%", "0", "-4", "most"].iter() {
+            assert_eq!(
+                bad.parse::<Penetration>().unwrap_err(),
+                BlJaError::InvalidPenetration(bad.to_string())
+            );
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,137 @@
+//! Saving a game in progress to a file, and resuming it later.  The whole engine is saved: the players and
+//! their money, the shoe in order with its cut card, the shuffling generator, and where the round is.
+
+use crate::engine::Engine;
+use crate::errors::BlJaError;
+use crate::Res;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+/// Bumped when a change to the game model makes older saves unreadable.
+const SAVE_VERSION: u32 = 1;
+
+#[derive(Serialize)]
+struct SaveGame<'a> {
+    version: u32,
+    engine: &'a Engine,
+}
+
+#[derive(Deserialize)]
+struct SavedGame {
+    version: u32,
+    engine: Engine,
+}
+
+/// Writes the game to the file, replacing it if it exists.
+pub fn save(engine: &Engine, path: &Path) -> Res<()> {
+    let json = serde_json::to_string_pretty(&SaveGame {
+        version: SAVE_VERSION,
+        engine,
+    })?;
+    fs::write(path, json)?;
+    Ok(())
+}
+
+/// Reads a game saved by `save`.  Play resumes from the command the engine was waiting on.
+pub fn load(path: &Path) -> Res<Engine> {
+    let json = fs::read_to_string(path)?;
+    let saved: SavedGame = serde_json::from_str(&json)
+        .map_err(|e| BlJaError::InvalidSave(format!("{}: {}", path.display(), e)))?;
+    if saved.version != SAVE_VERSION {
+        return Err(Box::new(BlJaError::InvalidSave(format!(
+            "{} is from version {} of the save format, expected {}",
+            path.display(),
+            saved.version,
+            SAVE_VERSION
+        ))));
+    }
+    Ok(saved.engine)
+}
+
+#[cfg(test)]
+mod savetests {
+    use super::*;
+    use crate::engine::{Command, Phase};
+    use crate::hand::Action;
+    use crate::player::Player;
+    use crate::rules::TableRules;
+    use crate::table::Table;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha12Rng;
+    use std::env;
+
+    fn temp_file(name: &str) -> std::path::PathBuf {
+        env::temp_dir().join(format!("blackjack-{}-{}.json", name, std::process::id()))
+    }
+
+    #[test]
+    fn save_and_load() -> Res<()> {
+        let table = Table::with_rng(
+            Player::seats(2, &[], 500.into()),
+            6,
+            TableRules::default(),
+            ChaCha12Rng::seed_from_u64(5),
+        )?;
+        let mut engine = Engine::new(table);
+        engine.start();
+        engine.handle(Command::Bet(25.into()))?;
+        engine.handle(Command::Bet(50.into()))?;
+
+        let path = temp_file("save");
+        save(&engine, &path)?;
+        let mut loaded = load(&path)?;
+        fs::remove_file(&path)?;
+
+        assert_eq!(loaded.phase(), engine.phase());
+        assert_eq!(loaded.table().deck, engine.table().deck);
+        for player in 0..2 {
+            let (player, loaded_player) = (
+                engine.table().player(player).unwrap().borrow(),
+                loaded.table().player(player).unwrap().borrow(),
+            );
+            assert_eq!(loaded_player.money(), player.money());
+            assert_eq!(loaded_player.get_hand(0), player.get_hand(0));
+        }
+
+        // Both games play out the same from here
+        loop {
+            let command = match engine.phase() {
+                Phase::Betting { .. } | Phase::GameOver => break,
+                Phase::Playing { .. } => Command::Act(Action::Stand),
+                Phase::Insurance { .. } => Command::Insure(0.into()),
+                _ => Command::Continue,
+            };
+            assert_eq!(loaded.handle(command)?, engine.handle(command)?);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn bad_saves() -> Res<()> {
+        let path = temp_file("bad");
+        fs::write(&path, "{\"version\": 1}")?;
+        let err = load(&path).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<BlJaError>(),
+            Some(BlJaError::InvalidSave(_))
+        ));
+
+        let engine = Engine::new(Table::new(
+            Player::seats(1, &[], 500.into()),
+            1,
+            TableRules::default(),
+        )?);
+        let json = serde_json::to_string(&SaveGame {
+            version: SAVE_VERSION + 1,
+            engine: &engine,
+        })?;
+        fs::write(&path, json)?;
+        let err = load(&path).unwrap_err();
+        fs::remove_file(&path)?;
+        assert!(err.to_string().contains("version 2"));
+
+        assert!(load(&temp_file("missing")).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,451 @@
+//! Basic strategy: the best play for a hand against the dealer's up card, for bots, simulations and the
+//! hint button.  The charts are the usual ones for four to eight decks, adjusted for whether the dealer hits
+//! soft 17 and whether doubling after a split is allowed.  Only plays the table allows are advised, so a
+//! double that is not allowed falls back to hitting or standing, and a surrender to the play without it.
+//!
+//! The expected value of each play is worked out for an infinite shoe, once the dealer is known not to have
+//! a natural, and without resplitting.  It is close enough to price a mistake.
+
+use crate::card::{BlackJackScore, Card};
+use crate::hand::Action::{self, Double, Hit, Split, Stand, Surrender};
+use crate::hand::{Hand, HandType};
+use crate::rules::{DoubleRule, TableRules};
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+
+// The plays for a situation, best first
+const H: &[Action] = &[Hit];
+const S: &[Action] = &[Stand];
+const DH: &[Action] = &[Double, Hit];
+const DS: &[Action] = &[Double, Stand];
+const RH: &[Action] = &[Surrender, Hit];
+const RS: &[Action] = &[Surrender, Stand];
+const P: &[Action] = &[Split];
+const RP: &[Action] = &[Surrender, Split];
+
+/// The best of the available actions for the hand against the dealer's up card.  `None` if no action is
+/// available.
+pub fn advise(
+    hand: &Hand,
+    up_card: &Card,
+    rules: &TableRules,
+    actions: &HashSet<Action>,
+) -> Option<Action> {
+    plays(hand, up_card, rules, actions.contains(&Split))
+        .iter()
+        .chain([Hit, Stand].iter())
+        .copied()
+        .find(|action| actions.contains(action))
+}
+
+/// The plays for the hand, best first.
+fn plays(hand: &Hand, up_card: &Card, rules: &TableRules, can_split: bool) -> &'static [Action] {
+    // Aces are 11
+    let up = up_card.score();
+    let h17 = rules.dealer_hits_soft_17;
+
+    if can_split {
+        if let Some(pair) = hand.card_iter().next().map(|card| card.score()) {
+            if pair == 8 && up == 11 && h17 {
+                return RP;
+            }
+            if splits(pair, up, rules.double_after_split) {
+                return P;
+            }
+        }
+    }
+
+    if is_soft(hand) {
+        soft(hand.score(), up, h17)
+    } else {
+        hard(hand.score(), up, h17)
+    }
+}
+
+fn is_soft(hand: &Hand) -> bool {
+    use HandType::*;
+    matches!(hand.hand_type(), Soft | SplitSoft | SplitAces | DoubledSoft)
+}
+
+/// Whether a pair of cards worth `pair` is split.
+fn splits(pair: usize, up: usize, das: bool) -> bool {
+    match pair {
+        11 | 8 => true,
+        9 => matches!(up, 2..=6 | 8 | 9),
+        7 => up <= 7,
+        6 => (3..=6).contains(&up) || (das && up == 2),
+        4 => das && (5..=6).contains(&up),
+        2 | 3 => (4..=7).contains(&up) || (das && up <= 3),
+        // Tens stand on 20, and fives double on 10
+        _ => false,
+    }
+}
+
+fn soft(total: usize, up: usize, h17: bool) -> &'static [Action] {
+    match total {
+        19 if h17 && up == 6 => DS,
+        19..=21 => S,
+        18 => match up {
+            2 if h17 => DS,
+            2 | 7 | 8 => S,
+            3..=6 => DS,
+            _ => H,
+        },
+        17 if (3..=6).contains(&up) => DH,
+        15 | 16 if (4..=6).contains(&up) => DH,
+        13 | 14 if (5..=6).contains(&up) => DH,
+        _ => H,
+    }
+}
+
+fn hard(total: usize, up: usize, h17: bool) -> &'static [Action] {
+    match total {
+        17 if h17 && up == 11 => RS,
+        17..=21 => S,
+        16 => match up {
+            2..=6 => S,
+            9..=11 => RH,
+            _ => H,
+        },
+        15 => match up {
+            2..=6 => S,
+            10 => RH,
+            11 if h17 => RH,
+            _ => H,
+        },
+        13 | 14 if up <= 6 => S,
+        12 if (4..=6).contains(&up) => S,
+        11 if up == 11 && !h17 => H,
+        11 => DH,
+        10 if up <= 9 => DH,
+        9 if (3..=6).contains(&up) => DH,
+        _ => H,
+    }
+}
+
+/// The bets won or lost on average by making the play, per bet.
+pub fn expected_value(hand: &Hand, up_card: &Card, rules: &TableRules, action: Action) -> f64 {
+    let odds = Odds::new(up_card.score(), rules);
+    let (total, soft) = (hand.score(), is_soft(hand));
+    match action {
+        Stand => odds.stand(total),
+        Hit => odds.hit(total, soft),
+        Double => 2. * odds.double(total, soft),
+        Surrender => -0.5,
+        Split => match hand.card_iter().next() {
+            Some(card) => 2. * odds.split(card.score()),
+            None => odds.stand(total),
+        },
+    }
+}
+
+/// The expected value lost by making the `chosen` play instead of the `advised` one, per bet.  Never less
+/// than zero, as the charts and the odds disagree on a few close plays.
+pub fn cost(
+    hand: &Hand,
+    up_card: &Card,
+    rules: &TableRules,
+    chosen: Action,
+    advised: Action,
+) -> f64 {
+    let ev = |action| expected_value(hand, up_card, rules, action);
+    (ev(advised) - ev(chosen)).max(0.)
+}
+
+/// Chance of drawing a card worth `value` from an infinite shoe.  Aces are 11.
+fn chance(value: usize) -> f64 {
+    if value == 10 {
+        4. / 13.
+    } else {
+        1. / 13.
+    }
+}
+
+/// The total after drawing a card worth `value`, and whether it is soft.
+fn draw(total: usize, soft: bool, value: usize) -> (usize, bool) {
+    let (mut total, mut soft) = match value {
+        11 if total + 11 > 21 => (total + 1, soft),
+        _ => (total + value, soft || value == 11),
+    };
+    if total > 21 && soft {
+        total -= 10;
+        soft = false;
+    }
+    (total, soft)
+}
+
+/// The chances of how the dealer's hand ends up against one up card, and the best way to play each total.
+struct Odds<'a> {
+    rules: &'a TableRules,
+    // Chance of the dealer finishing on 17 through 21, then of busting
+    dealer: [f64; 6],
+    // The value of the best play after hitting, by total and softness
+    best: RefCell<HashMap<(usize, bool), f64>>,
+}
+
+impl<'a> Odds<'a> {
+    fn new(up: usize, rules: &'a TableRules) -> Odds<'a> {
+        let mut dealer = [0.; 6];
+        let (total, soft) = draw(0, false, up);
+        // The dealer has already checked for a natural
+        let natural = match up {
+            10 => chance(11),
+            11 => chance(10),
+            _ => 0.,
+        };
+        for value in 2..=11 {
+            if total + value != 21 {
+                let (total, soft) = draw(total, soft, value);
+                Self::dealer_draws(
+                    total,
+                    soft,
+                    rules.dealer_hits_soft_17,
+                    chance(value) / (1. - natural),
+                    &mut dealer,
+                );
+            }
+        }
+        Odds {
+            rules,
+            dealer,
+            best: RefCell::new(HashMap::new()),
+        }
+    }
+
+    fn dealer_draws(total: usize, soft: bool, h17: bool, chance_of: f64, dealer: &mut [f64; 6]) {
+        if total > 21 {
+            dealer[5] += chance_of;
+        } else if total > 17 || (total == 17 && !(soft && h17)) {
+            dealer[total - 17] += chance_of;
+        } else {
+            for value in 2..=11 {
+                let (total, soft) = draw(total, soft, value);
+                Self::dealer_draws(total, soft, h17, chance_of * chance(value), dealer);
+            }
+        }
+    }
+
+    fn stand(&self, total: usize) -> f64 {
+        if total > 21 {
+            return -1.;
+        }
+        let bust = self.dealer[5];
+        (17..=21)
+            .zip(self.dealer.iter())
+            .fold(bust, |ev, (dealer, chance_of)| {
+                if total > dealer {
+                    ev + chance_of
+                } else if total < dealer {
+                    ev - chance_of
+                } else {
+                    ev
+                }
+            })
+    }
+
+    fn hit(&self, total: usize, soft: bool) -> f64 {
+        (2..=11)
+            .map(|value| {
+                let (total, soft) = draw(total, soft, value);
+                chance(value) * self.best(total, soft)
+            })
+            .sum()
+    }
+
+    /// The value of one doubled bet.
+    fn double(&self, total: usize, soft: bool) -> f64 {
+        (2..=11)
+            .map(|value| chance(value) * self.stand(draw(total, soft, value).0))
+            .sum()
+    }
+
+    /// The value of one of the split hands.
+    fn split(&self, pair: usize) -> f64 {
+        let (total, soft) = draw(0, false, pair);
+        (2..=11)
+            .map(|value| {
+                let (total, soft) = draw(total, soft, value);
+                let mut ev = self.stand(total);
+                if pair != 11 || self.rules.hit_split_aces {
+                    ev = ev.max(self.hit(total, soft));
+                }
+                if pair != 11 && self.doubles_after_split(total, soft) {
+                    ev = ev.max(2. * self.double(total, soft));
+                }
+                chance(value) * ev
+            })
+            .sum()
+    }
+
+    fn doubles_after_split(&self, total: usize, soft: bool) -> bool {
+        self.rules.double_after_split
+            && match self.rules.double {
+                DoubleRule::AnyTwo => true,
+                DoubleRule::NineToEleven => !soft && (9..=11).contains(&total),
+                DoubleRule::TenToEleven => !soft && (10..=11).contains(&total),
+            }
+    }
+
+    /// The better of standing or hitting again.
+    fn best(&self, total: usize, soft: bool) -> f64 {
+        if total > 21 {
+            return -1.;
+        }
+        if let Some(ev) = self.best.borrow().get(&(total, soft)) {
+            return *ev;
+        }
+        let ev = self.stand(total).max(self.hit(total, soft));
+        self.best.borrow_mut().insert((total, soft), ev);
+        ev
+    }
+}
+
+#[cfg(test)]
+mod strategytests {
+    use super::*;
+    use crate::card::Denomination::{self, *};
+    use crate::card::Suit;
+    use crate::rules::SurrenderRule;
+    use crate::Res;
+
+    fn advice(hand: &str, up: Denomination, rules: &TableRules) -> Res<Option<Action>> {
+        let hand: Hand = hand.parse()?;
+        let actions = hand.actions(rules);
+        Ok(advise(&hand, &Card::new(up, Suit::Hearts), rules, &actions))
+    }
+
+    #[test]
+    fn basic_strategy() -> Res<()> {
+        let rules = TableRules::default();
+        let plays = [
+            ("hard 16", Numerical(10), Hit),
+            ("hard 16", Numerical(6), Stand),
+            ("hard 12", Numerical(3), Hit),
+            ("hard 12", Numerical(4), Stand),
+            ("hard 11", Ace, Hit),
+            ("hard 11", Numerical(10), Double),
+            ("hard 9", Numerical(2), Hit),
+            ("hard 9", Numerical(3), Double),
+            ("hard 20", Ace, Stand),
+            ("soft 18", Numerical(2), Stand),
+            ("soft 18", Numerical(4), Double),
+            ("soft 18", Numerical(9), Hit),
+            ("soft 17", Numerical(3), Double),
+            ("soft 13", Numerical(4), Hit),
+            ("soft 13", Numerical(5), Double),
+            ("pair A", Numerical(10), Split),
+            ("pair 8", Ace, Split),
+            ("pair K", Numerical(6), Stand),
+            ("pair 5", Numerical(9), Double),
+            ("pair 9", Numerical(7), Stand),
+            ("pair 9", Numerical(8), Split),
+            ("pair 4", Numerical(5), Split),
+            ("pair 2", Numerical(2), Split),
+            ("pair 6", Numerical(7), Hit),
+        ];
+        for (hand, up, action) in plays.iter() {
+            assert_eq!(
+                advice(hand, *up, &rules)?,
+                Some(*action),
+                "{} against {}",
+                hand,
+                up
+            );
+        }
+
+        // A hand of three cards cannot be doubled
+        let mut hand: Hand = "5,4".parse()?;
+        hand.insert(crate::card::Visible::FacedUp(Card::new(
+            Numerical(2),
+            Suit::Clubs,
+        )));
+        let actions = hand.actions(&rules);
+        assert_eq!(
+            advise(
+                &hand,
+                &Card::new(Numerical(6), Suit::Clubs),
+                &rules,
+                &actions
+            ),
+            Some(Hit)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn table_rules() -> Res<()> {
+        let h17 = TableRules {
+            dealer_hits_soft_17: true,
+            surrender: SurrenderRule::Late,
+            ..TableRules::default()
+        };
+        assert_eq!(advice("hard 11", Ace, &h17)?, Some(Double));
+        assert_eq!(advice("soft 19", Numerical(6), &h17)?, Some(Double));
+        assert_eq!(advice("hard 17", Ace, &h17)?, Some(Surrender));
+        assert_eq!(advice("pair 8", Ace, &h17)?, Some(Surrender));
+        assert_eq!(advice("hard 16", Numerical(9), &h17)?, Some(Surrender));
+        assert_eq!(advice("hard 15", Numerical(9), &h17)?, Some(Hit));
+
+        // Without doubling after a split, small pairs are only split against a weak dealer
+        let no_das = TableRules {
+            double_after_split: false,
+            ..TableRules::default()
+        };
+        assert_eq!(advice("pair 4", Numerical(5), &no_das)?, Some(Hit));
+        assert_eq!(advice("pair 2", Numerical(2), &no_das)?, Some(Hit));
+        assert_eq!(advice("pair 6", Numerical(2), &no_das)?, Some(Hit));
+
+        // Doubles the table does not allow fall back to hitting or standing
+        let ten_eleven = TableRules {
+            double: crate::rules::DoubleRule::TenToEleven,
+            ..TableRules::default()
+        };
+        assert_eq!(advice("soft 18", Numerical(4), &ten_eleven)?, Some(Stand));
+        assert_eq!(advice("hard 9", Numerical(4), &ten_eleven)?, Some(Hit));
+        Ok(())
+    }
+
+    #[test]
+    fn expected_values() -> Res<()> {
+        let rules = TableRules::default();
+        let ev = |hand: &str, up: Denomination, action: Action| -> Res<f64> {
+            let hand: Hand = hand.parse()?;
+            Ok(expected_value(
+                &hand,
+                &Card::new(up, Suit::Hearts),
+                &rules,
+                action,
+            ))
+        };
+        let near = |ev: f64, expected: f64| (ev - expected).abs() < 0.005;
+
+        // Published values for an infinite shoe, dealer stands on soft 17
+        assert!(near(ev("hard 20", Numerical(6), Stand)?, 0.704));
+        assert!(near(ev("hard 16", Numerical(10), Stand)?, -0.540));
+        assert!(near(ev("hard 11", Numerical(6), Double)?, 0.667));
+        assert!(near(ev("soft 18", Numerical(6), Double)?, 0.382));
+        assert!(near(ev("pair 8", Numerical(10), Split)?, -0.490));
+        assert_eq!(ev("hard 16", Numerical(10), Surrender)?, -0.5);
+
+        // Against an ace, 11 is hit when the dealer stands on soft 17
+        assert!(ev("hard 11", Ace, Hit)? > ev("hard 11", Ace, Double)?);
+
+        // Hitting 20 is far worse than missing a soft double
+        let cost_of = |hand: &str, up: Denomination, chosen: Action, advised: Action| -> Res<f64> {
+            let hand: Hand = hand.parse()?;
+            Ok(cost(
+                &hand,
+                &Card::new(up, Suit::Hearts),
+                &rules,
+                chosen,
+                advised,
+            ))
+        };
+        let soft_double = cost_of("soft 18", Numerical(6), Stand, Double)?;
+        assert!(near(soft_double, 0.098));
+        assert!(cost_of("hard 20", Numerical(6), Hit, Stand)? > 10. * soft_double);
+        // Close plays never cost less than nothing
+        assert_eq!(cost_of("hard 16", Numerical(10), Hit, Stand)?, 0.);
+        Ok(())
+    }
+}
@@ -1,112 +1,126 @@
 use crate::card::{Card, Denomination, Suit, Visible};
-use crate::deck::Deck;
+use crate::deck::{Deck, DEMONS};
 use crate::deck_traits::Shufflable;
-use crate::player::Player;
+use crate::player::{Player, Status};
+use crate::rules::{DealingMode, TableRules};
 use crate::Res;
-use rand::Rng;
+use num::Rational64;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha12Rng;
 use std::cell::RefCell;
 use std::rc::Rc;
 
 /// A struct where the players, dealer, and deck is located.
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Table {
     pub(crate) dealer: Player,
     players: Vec<Rc<RefCell<Player>>>,
-    deck: Deck,
+    pub(crate) deck: Deck,
     num_of_decks: usize,
     pub(crate) reshuffle: bool,
+    rules: TableRules,
+    // Used for every shuffle and cut card placement, so a seeded table deals the same shoes.
+    rng: ChaCha12Rng,
+    // Cards in the shoe when it was shuffled, and dealt from it since.  Zero for a stacked deck.
+    #[cfg_attr(feature = "serde", serde(default))]
+    shoe_size: usize,
+    #[cfg_attr(feature = "serde", serde(default))]
+    dealt: usize,
+    #[cfg_attr(feature = "serde", serde(default))]
+    penetrations: Vec<Rational64>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    discards: Vec<Visible<Card>>,
+    // Cards in a shoe made from the discard tray during a round, until the engine reports it
+    #[cfg_attr(feature = "serde", serde(skip))]
+    discards_shuffled: Option<usize>,
 }
 
 impl Table {
-    /// Creates a new table, with initialized dealer, players, and a shuffled deck.
-    pub fn new(num_players: usize, num_decks: usize) -> Res<Table> {
-        let mut players = vec![];
-        for n in 1..=num_players {
-            let name = format!("Player {}", n);
-            players.push(Rc::new(RefCell::new(Player::new(name))));
-        }
+    /// Creates a new table, with the players seated, the dealer, and a shuffled deck.
+    pub fn new(players: Vec<Player>, num_decks: usize, rules: TableRules) -> Res<Table> {
+        Table::with_rng(players, num_decks, rules, ChaCha12Rng::from_entropy())
+    }
+
+    /// Creates a new table that shuffles with the given random number generator.  Tables made with the
+    /// same seed deal the same cards.
+    pub fn with_rng(
+        players: Vec<Player>,
+        num_decks: usize,
+        rules: TableRules,
+        rng: ChaCha12Rng,
+    ) -> Res<Table> {
         let mut table = Table {
             dealer: Player::new("Dealer".to_string()),
-            players,
+            players: players
+                .into_iter()
+                .map(|player| Rc::new(RefCell::new(player)))
+                .collect(),
             deck: Deck::new(num_decks)?,
             num_of_decks: num_decks,
             reshuffle: true,
+            rules,
+            rng,
+            shoe_size: 0,
+            dealt: 0,
+            penetrations: Vec::new(),
+            discards: Vec::new(),
+            discards_shuffled: None,
         };
         table.shuffle();
         Ok(table)
     }
 
+    /// Replaces the shoe with cards to be dealt in order, such as a `Deck` parsed from "AS KD 5H".  Once
+    /// they run out, play continues with shuffled shoes.
+    pub fn stack(&mut self, deck: Deck) {
+        self.deck = deck;
+        self.reshuffle = false;
+        self.shoe_size = 0;
+    }
+
     pub fn player(&self, player: usize) -> Option<&Rc<RefCell<Player>>> {
         self.players.get(player)
     }
 
-    /// Only used for initial card dealing at the beginning of round.
+    /// Only used for initial card dealing at the beginning of round.  Players who are out are skipped.  The
+    /// dealer only gets one card when there is no hole card.
     pub fn deal_players(&mut self) {
-        //todo: This section has a lot of repetition when it comes to getting a new card.  This
-        // was due to avoiding issues with the borrow checker. Find more efficient way.
-
-        for player in self.players.iter() {
+        let players: Vec<_> = self
+            .player_iter()
+            .filter(|player| player.borrow().status() == Status::Playing)
+            .collect();
+        for player in players.iter() {
             // Each player is guaranteed to have one hand at beginning of play
-            let player = Rc::clone(player);
-            let mut hand = player.borrow_mut();
-            let hand = hand
-                .hand_iter_mut()
-                .next()
-                .expect("Every player should have one hand");
-
-            let card = self.deck.deal(true);
-            let card = match card.denom() {
-                Denomination::Extra(_) => {
-                    self.reshuffle = true;
-                    self.deck.deal(true)
-                }
-                _ => card,
-            };
-
-            hand.insert(card);
+            let card = self.deal_card(true);
+            player
+                .borrow_mut()
+                .get_hand_mut(0)
+                .expect("Every player should have one hand")
+                .insert(card);
         }
 
         // Give the dealer one card faced up.
-        let card = self.deck.deal(true);
-        let card = match card.denom() {
-            Denomination::Extra(_) => {
-                self.reshuffle = true;
-                self.deck.deal(true)
-            }
-            _ => card,
-        };
-        self.dealer.hand_iter_mut().next().unwrap().insert(card);
+        let card = self.deal_card(true);
+        self.dealer.get_hand_mut(0).unwrap().insert(card);
 
         // Give out second cards
-        for player in self.players.iter() {
-            // Each player is guaranteed to have one hand at beginning of play
-            let player = Rc::clone(player);
-            let mut hand = player.borrow_mut();
-            let hand = hand
-                .hand_iter_mut()
-                .next()
-                .expect("Every player should have one hand");
-            let card = self.deck.deal(true);
-            let card = match card.denom() {
-                Denomination::Extra(_) => {
-                    self.reshuffle = true;
-                    self.deck.deal(true)
-                }
-                _ => card,
-            };
-            hand.insert(card);
+        for player in players.iter() {
+            let card = self.deal_card(true);
+            player
+                .borrow_mut()
+                .get_hand_mut(0)
+                .expect("Every player should have one hand")
+                .insert(card);
+        }
+
+        if self.rules.dealing != DealingMode::HoleCard {
+            return;
         }
 
         // Give dealer one card faced down
-        let card = self.deck.deal(false);
-        let card = match card.denom() {
-            Denomination::Extra(_) => {
-                self.reshuffle = true;
-                self.deck.deal(false)
-            }
-            _ => card,
-        };
-        self.dealer.hand_iter_mut().next().unwrap().insert(card);
+        let card = self.deal_card(false);
+        self.dealer.get_hand_mut(0).unwrap().insert(card);
     }
 
     #[inline]
@@ -114,15 +128,22 @@ impl Table {
         self.players.iter().map(|rc| Rc::clone(rc))
     }
 
-    #[inline]
+    /// Deals the next card, flagging a reshuffle when the cut card comes out.  A stacked deck, or a single
+    /// deck at a full table, can run out during a round, so the discard tray is shuffled to finish it.
     pub fn deal_card(&mut self, facedup: bool) -> Visible<Card> {
+        if self.deck.is_empty() {
+            self.shuffle_discards();
+        }
         let card = self.deck.deal(facedup);
         match card.denom() {
             Denomination::Extra(_) => {
                 self.reshuffle = true;
-                self.deck.deal(facedup)
+                self.deal_card(facedup)
+            }
+            _ => {
+                self.dealt += 1;
+                card
             }
-            _ => card,
         }
     }
 
@@ -131,25 +152,446 @@ impl Table {
         &mut self.dealer
     }
 
+    /// Shuffles a new shoe and places the cut card in it, recording how far into the old shoe play got.
     pub fn shuffle(&mut self) {
-        let mut deck = Deck::new(self.num_of_decks).unwrap();
+        if self.shoe_size > 0 {
+            self.penetrations
+                .push(Rational64::new(self.dealt as i64, self.shoe_size as i64));
+        }
 
-        deck.shuffle();
-        // Place the cut card 60-75 cards from the back. Not done for single deck
-        if self.num_of_decks > 1 {
-            let mut rng = rand::thread_rng();
-            let position = rng.gen_range(60..=75);
+        let mut deck = Deck::new(self.num_of_decks).unwrap();
+        deck.shuffle_with(&mut self.rng);
+        let len = deck.len();
+        let position = match self.rules.cut_card {
+            Some(cut_card) => Some(cut_card.cards_behind(len, &mut self.rng)),
+            // Place the cut card 60-75 cards from the back. Not done for single deck
+            None if self.num_of_decks > 1 => Some(self.rng.gen_range(60..=75)),
+            None => None,
+        };
+        if let Some(position) = position {
             let cut_card = Card::new(Denomination::Extra("shuffle"), Suit::Clubs);
-            let len = deck.len();
-            deck.insert(len - position, cut_card);
+            // Cards are dealt from the end, so the cards behind the cut card are at the start
+            deck.insert(position, cut_card);
             self.reshuffle = false;
         }
 
         self.deck = deck;
+        self.shoe_size = len;
+        self.dealt = 0;
+
+        // The discards go back into the new shoe, and the first cards of it are burned
+        self.discards.clear();
+        for _ in 0..self.rules.burn {
+            let card = self.deal_card(self.rules.burn_face_up);
+            self.discards.push(card);
+        }
+    }
+
+    /// Shuffles the discard tray into a shoe, leaving the cards in play where they are.  The full shoe is
+    /// shuffled before the next round.  A stacked deck may run out before anything has been discarded, in
+    /// which case a new shoe is used.
+    fn shuffle_discards(&mut self) {
+        if self.shoe_size > 0 {
+            self.penetrations
+                .push(Rational64::new(self.dealt as i64, self.shoe_size as i64));
+        }
+        let mut deck = match self.discards.len() {
+            0 => Deck::new(self.num_of_decks).unwrap(),
+            _ => Deck {
+                deck: self.discards.drain(..).map(|card| *card).collect(),
+                reshuffle: false,
+            },
+        };
+        deck.shuffle_with(&mut self.rng);
+
+        self.discards_shuffled = Some(deck.len());
+        self.deck = deck;
+        self.reshuffle = true;
+        // The partial shoe is not counted towards the penetration
+        self.shoe_size = 0;
+    }
+
+    /// The number of cards in a shoe made from the discard tray during a round, once per shuffle.
+    pub(crate) fn take_discards_shuffled(&mut self) -> Option<usize> {
+        self.discards_shuffled.take()
+    }
+
+    /// The cards out of play since the last shuffle, oldest first.  Burned cards are only seen if the table
+    /// burns them face up.
+    #[inline]
+    pub fn discards(&self) -> &[Visible<Card>] {
+        &self.discards
+    }
+
+    /// Puts cards picked up at the end of a round in the discard tray.
+    pub(crate) fn discard(&mut self, cards: Vec<Visible<Card>>) {
+        self.discards.extend(cards);
+    }
+
+    /// How many cards are left to deal in the shoe, not counting the cut card.
+    pub fn cards_remaining(&self) -> usize {
+        self.deck
+            .iter()
+            .filter(|card| !matches!(card.denom(), Denomination::Extra(_)))
+            .count()
+    }
+
+    /// How many cards of a rank are left in the shoe.
+    pub fn remaining(&self, rank: Denomination) -> usize {
+        self.deck.iter().filter(|card| card.denom() == rank).count()
+    }
+
+    /// How many of each rank are left in the shoe, from aces and faces down to twos.
+    pub fn remaining_ranks(&self) -> Vec<(Denomination, usize)> {
+        DEMONS
+            .iter()
+            .map(|&rank| (rank, self.remaining(rank)))
+            .collect()
+    }
+
+    /// Whether shoes are dealt down to a cut card, rather than reshuffled after every round.
+    #[inline]
+    pub fn uses_cut_card(&self) -> bool {
+        self.rules.cut_card.is_some() || self.num_of_decks > 1
+    }
+
+    /// The share of each finished shoe that was dealt before it was reshuffled, oldest first.
+    #[inline]
+    pub fn penetrations(&self) -> &[Rational64] {
+        &self.penetrations
+    }
+
+    /// The average share of a shoe dealt before reshuffling, once a shoe has been finished.
+    pub fn average_penetration(&self) -> Option<Rational64> {
+        if self.penetrations.is_empty() {
+            return None;
+        }
+        let total: Rational64 = self.penetrations.iter().sum();
+        Some(total / self.penetrations.len() as i64)
+    }
+
+    #[inline]
+    pub fn num_players(&self) -> usize {
+        self.players.len()
     }
 
     #[inline]
     pub fn decks(&self) -> usize {
         self.num_of_decks
     }
+
+    #[inline]
+    pub fn rules(&self) -> &TableRules {
+        &self.rules
+    }
+}
+
+#[cfg(test)]
+mod tabletests {
+    use super::*;
+
+    fn seeded(decks: usize, seed: u64) -> Table {
+        Table::with_rng(
+            Player::seats(1, &[], 500.into()),
+            decks,
+            TableRules::default(),
+            ChaCha12Rng::seed_from_u64(seed),
+        )
+        .unwrap()
+    }
+
+    fn cut_card(table: &Table) -> Option<usize> {
+        table
+            .deck
+            .iter()
+            .position(|c| c.denom() == Denomination::Extra("shuffle"))
+    }
+
+    #[test]
+    fn seeded_shuffle() {
+        for decks in [1, 6].iter() {
+            let mut first = seeded(*decks, 21);
+            let mut second = seeded(*decks, 21);
+            assert_eq!(first.deck, second.deck);
+            assert_eq!(cut_card(&first), cut_card(&second));
+
+            // Later shoes follow the same sequence
+            first.shuffle();
+            second.shuffle();
+            assert_eq!(first.deck, second.deck);
+            assert_eq!(cut_card(&first), cut_card(&second));
+
+            assert_ne!(first.deck, seeded(*decks, 22).deck);
+        }
+        assert!(cut_card(&seeded(1, 21)).is_none());
+        assert!(cut_card(&seeded(6, 21)).is_some());
+    }
+
+    #[test]
+    fn penetration() -> Res<()> {
+        use crate::rules::{CutCard, Penetration};
+
+        let rules = TableRules {
+            cut_card: Some(CutCard {
+                penetration: Penetration::Percent(50),
+                jitter: 0,
+            }),
+            ..TableRules::default()
+        };
+        // A single deck is dealt down to its cut card too
+        let mut table = Table::with_rng(
+            Player::seats(1, &[], 500.into()),
+            1,
+            rules,
+            ChaCha12Rng::seed_from_u64(8),
+        )?;
+        assert!(table.uses_cut_card());
+        assert_eq!(cut_card(&table), Some(26));
+        assert_eq!(table.average_penetration(), None);
+
+        for _ in 0..26 {
+            table.deal_card(true);
+            assert!(!table.reshuffle);
+        }
+        table.deal_card(true);
+        assert!(table.reshuffle);
+
+        // Hands in progress are finished before the shuffle
+        for _ in 0..3 {
+            table.deal_card(true);
+        }
+        table.shuffle();
+        table.deal_card(true);
+        table.shuffle();
+        assert_eq!(
+            table.penetrations(),
+            &[Rational64::new(30, 52), Rational64::new(1, 52)]
+        );
+        assert_eq!(table.average_penetration(), Some(Rational64::new(31, 104)));
+
+        // Stacked decks are not counted
+        table.stack("AS KD".parse()?);
+        table.deal_card(true);
+        table.shuffle();
+        assert_eq!(table.penetrations().len(), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn cut_card_depth() -> Res<()> {
+        use crate::rules::{CutCard, Penetration};
+
+        let rules = TableRules {
+            cut_card: Some(CutCard {
+                penetration: Penetration::Percent(75),
+                jitter: 0,
+            }),
+            ..TableRules::default()
+        };
+        let mut table = Table::with_rng(
+            Player::seats(1, &[], 500.into()),
+            6,
+            rules,
+            ChaCha12Rng::seed_from_u64(3),
+        )?;
+        // Three quarters of the shoe is dealt before the cut card comes out
+        for _ in 0..234 {
+            table.deal_card(true);
+            assert!(!table.reshuffle);
+        }
+        table.deal_card(true);
+        assert!(table.reshuffle);
+        table.shuffle();
+        assert_eq!(table.penetrations(), &[Rational64::new(235, 312)]);
+
+        // Without a cut card rule, a shoe is cut 60 to 75 cards from the back
+        let mut table = seeded(6, 3);
+        let mut dealt = 0;
+        while !table.reshuffle {
+            table.deal_card(true);
+            dealt += 1;
+        }
+        assert!((312 - 75..=312 - 59).contains(&dealt));
+        Ok(())
+    }
+
+    #[test]
+    fn out_of_cards() -> Res<()> {
+        let mut table = seeded(1, 5);
+        let mut discarded = vec![];
+        for _ in 0..20 {
+            discarded.push(table.deal_card(true));
+        }
+        table.discard(discarded.clone());
+        // The rest of the deck is still in play when it runs out
+        for _ in 0..32 {
+            table.deal_card(true);
+        }
+        assert!(table.deck.is_empty());
+        assert_eq!(table.take_discards_shuffled(), None);
+
+        let card = table.deal_card(true);
+        assert!(discarded.contains(&card));
+        assert_eq!(table.take_discards_shuffled(), Some(20));
+        assert_eq!(table.take_discards_shuffled(), None);
+        assert_eq!(table.cards_remaining(), 19);
+        assert!(table.discards().is_empty());
+        assert!(table.reshuffle);
+        assert_eq!(table.penetrations(), &[Rational64::from_integer(1)]);
+
+        // The next round starts with a full shoe, without counting the partial one
+        table.shuffle();
+        assert_eq!(table.cards_remaining(), 52);
+        assert_eq!(table.penetrations().len(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn burn() -> Res<()> {
+        let rules = TableRules {
+            burn: 3,
+            ..TableRules::default()
+        };
+        let mut table = Table::with_rng(
+            Player::seats(1, &[], 500.into()),
+            6,
+            rules.clone(),
+            ChaCha12Rng::seed_from_u64(2),
+        )?;
+        let mut unburned = Deck::new(6)?;
+        unburned.shuffle_with(&mut ChaCha12Rng::seed_from_u64(2));
+
+        assert_eq!(table.discards().len(), 3);
+        assert!(table.discards().iter().all(Visible::is_faced_down));
+        assert_eq!(table.deck.len(), 6 * 52 + 1 - 3);
+        // Dealing starts after the burned cards
+        let top = *unburned.iter().rev().nth(3).unwrap();
+        assert_eq!(table.deal_card(true).unwrap(), top);
+
+        let mut table = Table::new(
+            Player::seats(1, &[], 500.into()),
+            1,
+            TableRules {
+                burn_face_up: true,
+                ..rules
+            },
+        )?;
+        table.shuffle();
+        assert_eq!(table.discards().len(), 3);
+        assert!(table.discards().iter().all(Visible::is_faced_up));
+        assert_eq!(table.deck.len(), 52 - 3);
+        Ok(())
+    }
+
+    #[test]
+    fn shoe_composition() -> Res<()> {
+        use crate::engine::{Command, Engine, Phase};
+        use crate::hand::Action;
+
+        let mut table = seeded(2, 6);
+        assert_eq!(table.cards_remaining(), 104);
+        assert!(table.remaining_ranks().iter().all(|&(_, count)| count == 8));
+
+        let card = table.deal_card(true);
+        assert_eq!(table.remaining(card.denom()), 7);
+        assert_eq!(table.cards_remaining(), 103);
+
+        // Every card dealt in a round is in the discard tray at the end of it
+        let mut engine = Engine::new(seeded(2, 6));
+        engine.start();
+        engine.handle(Command::Bet(10.into()))?;
+        while engine.phase() != (Phase::Betting { player: 0 }) {
+            let command = match engine.phase() {
+                Phase::Playing { .. } => Command::Act(Action::Stand),
+                Phase::Insurance { .. } => Command::Insure(0.into()),
+                _ => Command::Continue,
+            };
+            engine.handle(command)?;
+        }
+        let table = engine.table();
+        assert_eq!(table.discards().len(), 104 - table.cards_remaining());
+        assert!(table.discards().len() >= 4);
+        for &(rank, count) in table.remaining_ranks().iter() {
+            let discarded = table
+                .discards()
+                .iter()
+                .filter(|card| card.denom() == rank)
+                .count();
+            assert_eq!(count + discarded, 8);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn stacked_deal() -> Res<()> {
+        // Pairs of eights for both players, and the dealer shows an ace with a ten underneath
+        let deck = "8S 8H AS 8D 8C KD 5H".parse()?;
+        let rules = TableRules::default();
+        let mut table = Table::new(Player::seats(2, &[], 500.into()), 1, rules.clone())?;
+        table.stack(deck);
+        for player in table.player_iter() {
+            player.borrow_mut().place_bet(10.into(), &rules)?;
+        }
+        table.deal_players();
+
+        for player in table.player_iter() {
+            let player = player.borrow();
+            let hand = player.get_hand(0).unwrap();
+            assert_eq!(hand.score(), 16);
+            assert!(player
+                .actions(0, &rules)
+                .contains(&crate::hand::Action::Split));
+        }
+        let dealer = table.dealer.get_hand(0).unwrap();
+        assert!(dealer.is_first_card_ace());
+        assert!(dealer.peek_for_natural());
+        assert_eq!(dealer.score(), 11);
+
+        assert_eq!(
+            table.deal_card(true),
+            Visible::FacedUp(Card::new(Denomination::Numerical(5), Suit::Hearts))
+        );
+        assert!(!table.reshuffle);
+
+        // A new shoe finishes the round once the stack runs out, and is reshuffled after
+        assert!(table.deal_card(true).is_faced_up());
+        assert!(table.reshuffle);
+        assert_eq!(table.deck.len(), 51);
+        Ok(())
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip() -> Res<()> {
+        use num::Rational64;
+
+        let mut table = seeded(6, 3);
+        let rules = TableRules::default();
+        for player in table.player_iter() {
+            player.borrow_mut().place_bet(10.into(), &rules)?;
+            player.borrow_mut().collect(Rational64::new(1, 3));
+        }
+        table.deal_players();
+
+        let json = serde_json::to_string(&table)?;
+        let mut loaded: Table = serde_json::from_str(&json)?;
+        assert_eq!(loaded.deck, table.deck);
+        assert_eq!(cut_card(&loaded), cut_card(&table));
+        assert_eq!(loaded.dealer.get_hand(0), table.dealer.get_hand(0));
+        for (player, loaded_player) in table.player_iter().zip(loaded.player_iter()) {
+            let (player, loaded_player) = (player.borrow(), loaded_player.borrow());
+            assert_eq!(loaded_player.money(), Rational64::new(1471, 3));
+            assert_eq!(loaded_player.get_hand(0), player.get_hand(0));
+        }
+
+        // The random number generator carries on where it was
+        table.shuffle();
+        loaded.shuffle();
+        assert_eq!(loaded.deck, table.deck);
+
+        // The cut card is the only extra card
+        let json = json.replace("\"shuffle\"", "\"joker\"");
+        assert!(serde_json::from_str::<Table>(&json).is_err());
+        Ok(())
+    }
 }